@@ -1,12 +1,36 @@
+use serde::{Serialize, Deserialize};
+
 use std::collections::HashMap;
+use std::fmt;
 
 pub type RawInnovation = (usize, usize, Type);
 
-#[derive(Hash, Eq, PartialEq, Clone, Copy, Debug)]
+#[derive(Hash, Eq, PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum Type {
     Neuron,
     Connector
 }
+
+#[derive(Debug, PartialEq)]
+pub enum InnovationError {
+    Duplicate(RawInnovation),
+    InputsNotSequential,
+    OutputsNotSequential,
+}
+
+impl fmt::Display for InnovationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InnovationError::Duplicate(innovation) => write!(f, "innovation {:?} already exists", innovation),
+            InnovationError::InputsNotSequential => write!(f, "inputs must be numbered 1, 2, 3..."),
+            InnovationError::OutputsNotSequential => write!(f, "outputs must be numbered right after the inputs"),
+        }
+    }
+}
+
+impl std::error::Error for InnovationError {}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Innovation {
     pub from: usize,
     pub to: usize,
@@ -14,43 +38,75 @@ pub struct Innovation {
     pub kind: Type,
 }
 
+// The map is derived from the innovations vec, so only the vec is saved and the map is rebuilt on load
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "SavedTable")]
 pub struct InnovationTable {
     pub innovations: Vec<Innovation>,
+    #[serde(skip)]
     pub innovation_map: HashMap<RawInnovation, usize>, // (from, to, neuron) -> id. You can then get the innovation from the innovations vec
+    pub split_neurons: HashMap<usize, Vec<usize>>, // Id of a (from, to, Neuron) innovation -> the neurons that split the connection, oldest first
     pub neuron_levels: (Vec<usize>, Vec<usize>),
     neuron_counter: usize,
 }
 
+#[derive(Deserialize)]
+struct SavedTable {
+    innovations: Vec<Innovation>,
+    split_neurons: HashMap<usize, Vec<usize>>,
+    neuron_levels: (Vec<usize>, Vec<usize>),
+    neuron_counter: usize,
+}
+
+impl From<SavedTable> for InnovationTable {
+    fn from(saved: SavedTable) -> Self {
+        let innovation_map = saved.innovations.iter()
+            .map(|innovation| ((innovation.from, innovation.to, innovation.kind), innovation.id))
+            .collect();
+
+        InnovationTable {
+            innovations: saved.innovations,
+            innovation_map,
+            split_neurons: saved.split_neurons,
+            neuron_levels: saved.neuron_levels,
+            neuron_counter: saved.neuron_counter,
+        }
+    }
+}
+
+impl Default for InnovationTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl InnovationTable {
     pub fn new() -> InnovationTable {
         InnovationTable {
             innovations: Vec::new(),
             innovation_map: HashMap::new(),
+            split_neurons: HashMap::new(),
             neuron_levels: (Vec::new(), Vec::new()),
             neuron_counter: 0,
         }
     }
 
-    pub fn init(neuron_levels: (Vec<usize>, Vec<usize>), starting_innovations: Vec<(usize, usize, Type)>) -> InnovationTable {
+    pub fn init(neuron_levels: (Vec<usize>, Vec<usize>), starting_innovations: Vec<(usize, usize, Type)>) -> Result<InnovationTable, InnovationError> {
         let mut table = InnovationTable::new();
 
-        table.set_levels(neuron_levels.0, neuron_levels.1);
+        table.set_levels(neuron_levels.0, neuron_levels.1)?;
 
         for raw_innovation in starting_innovations.iter() {
-            table.add_innovation(*raw_innovation);
+            table.add_innovation(*raw_innovation)?;
         }
 
-        table
+        Ok(table)
     }
 
-    pub fn add_innovation(&mut self, innovation: RawInnovation) {
-
-        #[cfg(debug_assertions)]
-        {
-            match self.get_innovation((innovation.0, innovation.1, innovation.2)) {
-                Some(_) => panic!("Innovation already exists"),
-                None => (),
-            }
+    // Returns the id of the new innovation
+    pub fn add_innovation(&mut self, innovation: RawInnovation) -> Result<usize, InnovationError> {
+        if self.get_innovation(innovation).is_some() {
+            return Err(InnovationError::Duplicate(innovation));
         }
 
         self.innovations.push(
@@ -66,37 +122,67 @@ impl InnovationTable {
             (innovation.0, innovation.1, innovation.2),
             self.innovations.len() - 1
         );
+
+        Ok(self.innovations.len() - 1)
     }
 
     pub fn get_innovation(&self, innovation: RawInnovation) -> Option<&usize> {
-        match self.innovation_map.get(&(innovation.0, innovation.1, innovation.2)) {
-            Some(index) => Some(index),
-            None => None,
-        }
+        self.innovation_map.get(&(innovation.0, innovation.1, innovation.2))
     }
 
-    pub fn set_levels(&mut self, input_level: Vec<usize>, output_level: Vec<usize>) {
-        #[cfg(debug_assertions)]
-        {
-            for i in 0..input_level.len() {
-                if i+1 != input_level[i] {
-                    panic!("inputs not sequential at innovation");
-                }
+    pub fn set_levels(&mut self, input_level: Vec<usize>, output_level: Vec<usize>) -> Result<(), InnovationError> {
+        for (i, input) in input_level.iter().enumerate() {
+            if i + 1 != *input {
+                return Err(InnovationError::InputsNotSequential);
             }
+        }
 
-            for i in 0..output_level.len() {
-                if i+input_level.len()+1 != output_level[i] {
-                    panic!("outputs not sequential at innovation");
-                }
-            }  
+        for (i, output) in output_level.iter().enumerate() {
+            if i + input_level.len() + 1 != *output {
+                return Err(InnovationError::OutputsNotSequential);
+            }
         }
 
         self.neuron_counter = input_level.len() + output_level.len();
         self.neuron_levels = (input_level, output_level);
+
+        Ok(())
     }
 
     pub fn inc_neuron(&mut self) -> usize {
         self.neuron_counter += 1;
         self.neuron_counter
     }
+
+    // Neuron that goes between from and to when the connection is split
+    // The split is recorded as a (from, to, Neuron) innovation, so every genome splitting the same connection gets the same neuron
+    // taken says which neurons the splitting genome already has, the first recorded neuron it doesnt have is reused
+    // and if it has all of them a new one is recorded, so genomes hitting the same conflict still line up
+    pub fn split_connection<F>(&mut self, from: usize, to: usize, taken: F) -> Result<usize, InnovationError> where F: Fn(usize) -> bool {
+        let id = match self.get_innovation((from, to, Type::Neuron)) {
+            Some(id) => *id,
+            None => self.add_innovation((from, to, Type::Neuron))?,
+        };
+
+        if let Some(neuron) = self.split_neurons.get(&id).and_then(|neurons| neurons.iter().find(|neuron| !taken(**neuron))) {
+            return Ok(*neuron);
+        }
+
+        // Hidden neurons given with the starting innovations dont move the counter, so a new neuron can be taken too
+        let mut neuron = self.inc_neuron();
+
+        while taken(neuron) {
+            neuron = self.inc_neuron();
+        }
+
+        self.split_neurons.entry(id).or_default().push(neuron);
+
+        Ok(neuron)
+    }
+
+    // Forgets which neurons came from which splits, the next split of any connection gets a new neuron
+    // Called every generation when splits should only be shared within a generation, as in the NEAT paper
+    pub fn clear_splits(&mut self) {
+        self.split_neurons.clear();
+    }
 }
\ No newline at end of file
@@ -1,8 +1,7 @@
-use neatcore::Core;
-use network::*;
-use innovation::*;
+use neatcore::{Core, NeatConfig};
 
-const POPULATION: usize = 1;
+const POPULATION: usize = 50;
+const GENERATIONS: usize = 100;
 
 fn main() {    
     let mut core = Core::init(
@@ -10,11 +9,28 @@ fn main() {
         None,
         None,
         (1, 1), /* # of inputs, # of outputs, (outputs cant be 0) */
-    );
+        NeatConfig::default(),
+    ).unwrap();
 
-    //core.train();
+    // Fit y = 0.5x + 1
+    for _ in 0..GENERATIONS {
+        let best = core.evolve(|network| {
+            let mut error = 0.0;
+
+            for i in -5..=5 {
+                let x = i as f64;
+                let y = network.run(vec![x]).unwrap()[0];
+
+                error += (y - (0.5 * x + 1.0)).powi(2);
+            }
+
+            -error
+        }).unwrap();
+
+        log(&(core.generation(), best));
+    }
 }
 
 fn log<T>(var: &T) where T: std::fmt::Debug {
     println!("{:?}", var);
-}
\ No newline at end of file
+}
@@ -0,0 +1,140 @@
+use crate::{Core, NeatError};
+use crate::save::check_weights;
+
+use std::path::Path;
+
+impl Core {
+    // Writes the whole run, population, innovation table, species and rng, so it can continue with Core::resume
+    // Written to a temporary file first, so an interrupted write never replaces a good checkpoint
+    // Refused if a weight isnt finite, the checkpoint couldnt be resumed and would replace one that can
+    pub fn checkpoint<P>(&self, path: P) -> Result<(), NeatError> where P: AsRef<Path> {
+        let genomes = self.gen_arr.iter()
+            .chain(self.species.iter().map(|species| &species.exemplar))
+            .chain(self.champion.iter().map(|(genome, _)| genome));
+
+        for genome in genomes {
+            check_weights(genome)?;
+        }
+
+        let path = path.as_ref();
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+
+        std::fs::write(&temporary, serde_json::to_string(self)?)?;
+        std::fs::rename(&temporary, path)?;
+
+        Ok(())
+    }
+
+    // Continues exactly where the checkpoint was written, the same seed gives the same generations as an uninterrupted run
+    pub fn resume<P>(path: P) -> Result<Self, NeatError> where P: AsRef<Path> {
+        let core: Self = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        core.config.validate()?;
+        core.validate()?;
+
+        Ok(core)
+    }
+
+    // A truncated or edited checkpoint can still parse, but would panic on the next generation
+    fn validate(&self) -> Result<(), NeatError> {
+        if self.gen_arr.len() != self.population || self.fit_arr.len() != self.population {
+            return Err(NeatError::Checkpoint(format!(
+                "population is {} but there are {} genomes and {} fitnesses", self.population, self.gen_arr.len(), self.fit_arr.len(),
+            )));
+        }
+
+        for species in self.species.iter() {
+            if let Some(member) = species.members.iter().find(|member| **member >= self.population) {
+                return Err(NeatError::Checkpoint(format!("species member {} is outside the population", member)));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Called after every generation, writes a checkpoint every config.checkpoint_interval generations
+    pub(crate) fn periodic_checkpoint(&self) -> Result<(), NeatError> {
+        let interval = self.config.checkpoint_interval;
+
+        match &self.config.checkpoint_path {
+            Some(path) if interval != 0 && self.generation.is_multiple_of(interval) => self.checkpoint(path),
+            _ => Ok(()),
+        }
+    }
+}
+
+// JSON has no inf or NaN, but fitness functions can return them (f64::NEG_INFINITY for an invalid network)
+// so non finite fitnesses are written as strings
+pub(crate) mod float {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum Float {
+        Finite(f64),
+        NonFinite(String),
+    }
+
+    impl From<f64> for Float {
+        fn from(value: f64) -> Self {
+            if value.is_finite() {
+                Float::Finite(value)
+            } else {
+                Float::NonFinite(value.to_string())
+            }
+        }
+    }
+
+    impl Float {
+        fn value<E>(self) -> Result<f64, E> where E: serde::de::Error {
+            match self {
+                Float::Finite(value) => Ok(value),
+                Float::NonFinite(text) => text.parse().map_err(|_| E::custom(format!("invalid float {:?}", text))),
+            }
+        }
+    }
+
+    pub fn serialize<S>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        Float::from(*value).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<f64, D::Error> where D: Deserializer<'de> {
+        Float::deserialize(deserializer)?.value()
+    }
+
+    pub mod vec {
+        use super::Float;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S>(values: &[f64], serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+            serializer.collect_seq(values.iter().map(|value| Float::from(*value)))
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<f64>, D::Error> where D: Deserializer<'de> {
+            Vec::<Float>::deserialize(deserializer)?
+                .into_iter()
+                .map(Float::value)
+                .collect()
+        }
+    }
+
+    // For the champion, Option<(Genome, f64)>
+    pub mod champion {
+        use super::Float;
+        use network::Genome;
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        pub fn serialize<S>(champion: &Option<(Genome, f64)>, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+            champion.as_ref()
+                .map(|(genome, fitness)| (genome, Float::from(*fitness)))
+                .serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<(Genome, f64)>, D::Error> where D: Deserializer<'de> {
+            match Option::<(Genome, Float)>::deserialize(deserializer)? {
+                Some((genome, fitness)) => Ok(Some((genome, fitness.value()?))),
+                None => Ok(None),
+            }
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use crate::WeightDistribution;
+use network::{Activation, HIDDEN_ACTIVATION, OUTPUT_ACTIVATION};
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+use std::path::Path;
+
+// Every evolution parameter, loadable from a JSON or TOML file
+// Missing fields in a file fall back to the defaults
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct NeatConfig {
+    // Compare
+    pub c1: f64, // Excess genes
+    pub c2: f64, // Disjoint genes
+    pub c3: f64, // Average weight difference
+    pub compat_threshold: f64,
+    pub stagnation_limit: usize, // Generations without improvement before a species is removed
+
+    // Mutation
+    pub add_node_rate: f64,
+    pub add_connection_rate: f64,
+    pub add_connection_attempts: usize, // Random picks before falling back to the exact list of absent connections
+    pub new_connection_weight: WeightDistribution,
+    pub toggle_rate: f64, // Chance to flip the enabled flag of a random gene
+    pub delete_connection_rate: f64,
+    pub delete_neuron_rate: f64, // Removes a hidden neuron with all of its connectors
+    pub weight_change_rate: f64, // Chance a genome gets its weights mutated at all
+    pub weight_perturb_rate: f64, // Per enabled gene, chance to be nudged by a Gaussian
+    pub weight_replace_rate: f64, // Per enabled gene, chance to get a fresh weight from new_connection_weight instead
+    pub weight_perturb_sigma: f64,
+    pub weight_range: Option<(f64, f64)>, // (min, max) mutated weights are clamped to, None leaves them unbounded
+    pub splits_per_generation: bool, // Splits of the same connection only share a neuron within a generation, as in the NEAT paper
+
+    // Selection
+    pub survival_rate: f64,
+
+    // Elitism
+    pub elitism: usize, // Best genomes of each species copied unchanged
+    pub elitism_min_size: usize, // Species need this many members to keep elites
+
+    // Crossover
+    pub crossover_rate: f64,
+    pub disable_chance: f64, // Chance a gene disabled in either parent stays disabled
+
+    // Network
+    pub hidden_activation: Activation,
+    pub output_activation: Activation,
+    pub recurrent: bool, // Lets mutations add back edges and self loops
+
+    // Backpropogation
+    pub learning_rate: f64,
+
+    // Same seed, config and fitness function give the same run, None seeds from entropy
+    pub seed: Option<u64>,
+
+    // Worker threads for evolve_parallel
+    pub threads: usize,
+
+    // Checkpoints
+    pub checkpoint_interval: usize, // Generations between checkpoints, 0 never writes one
+    pub checkpoint_path: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Toml(toml::de::Error),
+    UnknownFormat(String),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(error) => write!(f, "could not read config: {}", error),
+            ConfigError::Json(error) => write!(f, "invalid json config: {}", error),
+            ConfigError::Toml(error) => write!(f, "invalid toml config: {}", error),
+            ConfigError::UnknownFormat(extension) => write!(f, "unknown config format: {:?}, expected json or toml", extension),
+            ConfigError::Invalid(reason) => write!(f, "invalid config: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Default for NeatConfig {
+    fn default() -> Self {
+        Self {
+            c1: 1.0,
+            c2: 0.5,
+            c3: 0.5,
+            compat_threshold: 3.0,
+            stagnation_limit: 15,
+
+            add_node_rate: 0.04, // 4%
+            add_connection_rate: 0.08, // 8%
+            add_connection_attempts: 20,
+            new_connection_weight: WeightDistribution::Uniform { min: -1.0, max: 1.0 },
+            toggle_rate: 0.01, // 1%
+            delete_connection_rate: 0.0,
+            delete_neuron_rate: 0.0,
+            weight_change_rate: 0.8, // 80%, as in the NEAT paper
+            weight_perturb_rate: 0.9, // 90%, the rest of the genes are replaced
+            weight_replace_rate: 0.1, // 10%
+            weight_perturb_sigma: 0.5,
+            weight_range: None,
+            splits_per_generation: false,
+
+            survival_rate: 0.5, // 50%
+
+            elitism: 1,
+            elitism_min_size: 5,
+
+            crossover_rate: 0.75, // 75%
+            disable_chance: 0.75, // 75%
+
+            hidden_activation: HIDDEN_ACTIVATION,
+            output_activation: OUTPUT_ACTIVATION,
+            recurrent: false,
+
+            learning_rate: 0.01,
+
+            seed: None,
+
+            threads: 1,
+
+            checkpoint_interval: 0,
+            checkpoint_path: None,
+        }
+    }
+}
+
+impl NeatConfig {
+    pub fn from_json(json: &str) -> Result<Self, ConfigError> {
+        let config: Self = serde_json::from_str(json).map_err(ConfigError::Json)?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    pub fn from_toml(toml: &str) -> Result<Self, ConfigError> {
+        let config: Self = toml::from_str(toml).map_err(ConfigError::Toml)?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    // Format is picked by the file extension
+    pub fn load<P>(path: P) -> Result<Self, ConfigError> where P: AsRef<Path> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Self::from_json(&contents),
+            Some("toml") => Self::from_toml(&contents),
+            extension => Err(ConfigError::UnknownFormat(extension.unwrap_or("").to_string())),
+        }
+    }
+
+    pub fn clamp_weight(&self, weight: f64) -> f64 {
+        match self.weight_range {
+            Some((min, max)) => weight.clamp(min, max),
+            None => weight,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let rates = [
+            ("add_node_rate", self.add_node_rate),
+            ("add_connection_rate", self.add_connection_rate),
+            ("toggle_rate", self.toggle_rate),
+            ("delete_connection_rate", self.delete_connection_rate),
+            ("delete_neuron_rate", self.delete_neuron_rate),
+            ("weight_change_rate", self.weight_change_rate),
+            ("weight_perturb_rate", self.weight_perturb_rate),
+            ("weight_replace_rate", self.weight_replace_rate),
+            ("survival_rate", self.survival_rate),
+            ("crossover_rate", self.crossover_rate),
+            ("disable_chance", self.disable_chance),
+        ];
+
+        for (name, rate) in rates {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(ConfigError::Invalid(format!("{} must be between 0 and 1, got {}", name, rate)));
+            }
+        }
+
+        let coefficients = [("c1", self.c1), ("c2", self.c2), ("c3", self.c3), ("learning_rate", self.learning_rate), ("weight_perturb_sigma", self.weight_perturb_sigma)];
+
+        for (name, coefficient) in coefficients {
+            if !coefficient.is_finite() || coefficient < 0.0 {
+                return Err(ConfigError::Invalid(format!("{} must be a non negative number, got {}", name, coefficient)));
+            }
+        }
+
+        if !self.compat_threshold.is_finite() || self.compat_threshold <= 0.0 {
+            return Err(ConfigError::Invalid(format!("compat_threshold must be positive, got {}", self.compat_threshold)));
+        }
+
+        if self.threads == 0 {
+            return Err(ConfigError::Invalid("threads must be at least 1".to_string()));
+        }
+
+        if !self.new_connection_weight.is_valid() {
+            return Err(ConfigError::Invalid(format!("new_connection_weight is not a valid distribution, got {:?}", self.new_connection_weight)));
+        }
+
+        if self.checkpoint_interval != 0 && self.checkpoint_path.is_none() {
+            return Err(ConfigError::Invalid("checkpoint_interval needs a checkpoint_path".to_string()));
+        }
+
+        if self.weight_perturb_rate + self.weight_replace_rate > 1.0 {
+            return Err(ConfigError::Invalid("weight_perturb_rate and weight_replace_rate cannot add up to more than 1".to_string()));
+        }
+
+        if let Some((min, max)) = self.weight_range {
+            if !min.is_finite() || !max.is_finite() || min >= max {
+                return Err(ConfigError::Invalid(format!("weight_range must be an increasing (min, max) pair, got {:?}", (min, max))));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use crate::NeatConfig;
+use network::Genome;
+
+use rand::Rng;
+
+// Aligns both parents by innovation id
+// Matching genes are inherited randomly, disjoint and excess genes come from the fitter parent
+// On equal fitness parent_1 counts as the fitter one, so the child always keeps the topology of a single parent (merging both could create cycles)
+pub fn crossover<R>(parent_1: &Genome, fitness_1: f64, parent_2: &Genome, fitness_2: f64, config: &NeatConfig, rng: &mut R) -> Genome where R: Rng {
+    let (fitter, other) = if fitness_2 > fitness_1 {
+        (parent_2, parent_1)
+    } else {
+        (parent_1, parent_2)
+    };
+
+    let mut child = Genome::new();
+
+    for gene in fitter.iter() {
+        let mut gene = *gene;
+
+        if let Some(other_gene) = other.get(gene.innovation) {
+            if rng.gen_bool(0.5) {
+                gene.weight = other_gene.weight;
+            }
+
+            // A gene disabled in either parent has a chance to stay disabled
+            gene.enabled = if !gene.enabled || !other_gene.enabled {
+                rng.gen::<f64>() >= config.disable_chance
+            } else {
+                true
+            };
+        }
+
+        child.insert(gene);
+    }
+
+    child
+}
@@ -0,0 +1,37 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// Where new weights are drawn from
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum WeightDistribution {
+    Constant(f64),
+    Uniform { min: f64, max: f64 },
+    Gaussian { mean: f64, std_dev: f64 },
+}
+
+impl WeightDistribution {
+    pub fn sample<R>(&self, rng: &mut R) -> f64 where R: Rng {
+        match *self {
+            WeightDistribution::Constant(weight) => weight,
+            WeightDistribution::Uniform { min, max } => rng.gen_range(min..max),
+            WeightDistribution::Gaussian { mean, std_dev } => mean + std_dev * gaussian(rng),
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            WeightDistribution::Constant(weight) => weight.is_finite(),
+            WeightDistribution::Uniform { min, max } => min.is_finite() && max.is_finite() && min < max,
+            WeightDistribution::Gaussian { mean, std_dev } => mean.is_finite() && std_dev.is_finite() && std_dev >= 0.0,
+        }
+    }
+}
+
+// Standard normal sample with the Box-Muller transform
+pub fn gaussian<R>(rng: &mut R) -> f64 where R: Rng {
+    // 1 - gen keeps u1 out of 0, where the log is infinite
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
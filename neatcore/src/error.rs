@@ -0,0 +1,68 @@
+use crate::ConfigError;
+use innovation::InnovationError;
+use network::NetworkError;
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum NeatError {
+    Config(ConfigError),
+    Network(NetworkError),
+    Innovation(InnovationError),
+    NoOutputs,
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    NonFiniteWeight(usize), // Innovation id of a NaN or infinite weight, which JSON cant hold
+    Checkpoint(String), // Checkpoint that parses but doesnt describe a consistent run
+    NoSamples, // Backprop called with an empty sample list
+    Diverged, // Backprop produced a non finite loss or weight, the genome is left unchanged
+}
+
+impl fmt::Display for NeatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NeatError::Config(error) => write!(f, "{}", error),
+            NeatError::Network(error) => write!(f, "network: {}", error),
+            NeatError::Innovation(error) => write!(f, "innovation: {}", error),
+            NeatError::NoOutputs => write!(f, "levels.1 cannot be 0, a network needs at least one output"),
+            NeatError::Io(error) => write!(f, "io: {}", error),
+            NeatError::Json(error) => write!(f, "json: {}", error),
+            NeatError::NonFiniteWeight(id) => write!(f, "gene {} has a non finite weight and cannot be saved", id),
+            NeatError::Checkpoint(reason) => write!(f, "invalid checkpoint: {}", reason),
+            NeatError::NoSamples => write!(f, "backprop needs at least one sample"),
+            NeatError::Diverged => write!(f, "backprop diverged, try a lower learning_rate"),
+        }
+    }
+}
+
+impl std::error::Error for NeatError {}
+
+impl From<ConfigError> for NeatError {
+    fn from(error: ConfigError) -> Self {
+        NeatError::Config(error)
+    }
+}
+
+impl From<NetworkError> for NeatError {
+    fn from(error: NetworkError) -> Self {
+        NeatError::Network(error)
+    }
+}
+
+impl From<InnovationError> for NeatError {
+    fn from(error: InnovationError) -> Self {
+        NeatError::Innovation(error)
+    }
+}
+
+impl From<std::io::Error> for NeatError {
+    fn from(error: std::io::Error) -> Self {
+        NeatError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for NeatError {
+    fn from(error: serde_json::Error) -> Self {
+        NeatError::Json(error)
+    }
+}
@@ -1,61 +1,50 @@
-use network::{NeuralNetwork, Layers, Genome};
-use innovation::{InnovationTable, RawInnovation, Type};
-use timer::Timer;
-
-use rand::Rng;
-use serde::Serialize;
-
-use std::{collections::HashSet};
-
-
-// Compare
-    const C1: f64 = 1.0;
-    const C2: f64 = 0.5;
-    const C3: f64 = 0.5;
-
-// Mutation
-    const WGHT_CHNG_RNG: (f64, f64) = (0.25, -0.25);
-
-    // Odds
-        const ADD_NODE: f64 = 1.0;//0.04; // 4%
-        const ADD_CONN: f64 = 1.0;//0.08; // 8%
-        const CHNG_WEIGHT: f64 = 1.0;//0.12; // 12%
-
-// Backpropogation
-const LRN_RATE: f64 = 0.01;
-
-struct Species {
-    exemplar: Genome,
-    members: Vec<usize>,
-    stagnant_generations: usize,
-    fitness: f64,
-}
+mod species;
+mod crossover;
+mod config;
+mod error;
+mod save;
+mod checkpoint;
+mod distribution;
+
+pub use crossover::crossover;
+pub use config::{NeatConfig, ConfigError};
+pub use error::NeatError;
+pub use save::SavedNetwork;
+pub use distribution::WeightDistribution;
+pub use species::{distance, shared_fitness, allocate_offspring};
+use distribution::gaussian;
+use species::Species;
+use network::{NeuralNetwork, NetworkError, Layers, Gene, Genome};
+use innovation::{InnovationTable, InnovationError, Type};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Serialize, Deserialize};
+
+use std::{collections::HashSet, thread};
 
 #[derive(Serialize, Debug)]
-struct JSON_network {
+struct JsonNetwork {
     nodes: Vec<usize>,
     layers: Vec<Vec<usize>>,
     connections: Vec<(usize, usize)>,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Core {
     population: usize,
     gen_arr: Vec<Genome>,
+    #[serde(with = "checkpoint::float::vec")]
     fit_arr: Vec<f64>,
     table: InnovationTable,
     output_set: HashSet<usize>,
     species: Vec<Species>,
-}
-
-impl Species {
-    fn init(exemplar: Genome,) -> Self {
-        Self {
-            exemplar,
-            members: Vec::new(),
-            stagnant_generations: 0,
-            fitness: f64::NAN,
-        }
-    }
+    generation: usize,
+    #[serde(with = "checkpoint::float::champion")]
+    champion: Option<(Genome, f64)>, // Best genome ever evaluated and its fitness
+    full_genomes: usize, // Genomes of the last reproduction that had no connection left to add
+    config: NeatConfig,
+    rng: ChaCha8Rng, // Every stochastic operation draws from this, so runs can be reproduced from the seed
 }
 
 impl Core {
@@ -67,6 +56,11 @@ impl Core {
             table: InnovationTable::new(),
             output_set: HashSet::new(),
             species: Vec::new(),
+            generation: 0,
+            champion: None,
+            full_genomes: 0,
+            config: NeatConfig::default(),
+            rng: ChaCha8Rng::from_entropy(),
         }
     }
 
@@ -75,20 +69,21 @@ impl Core {
         default_genome: Option<&Genome>, 
         innovations: Option<Vec<(usize, usize)>>, // No Type becuase innovation isnt imported in main 
         levels: (usize, usize),
-    ) -> Self {
+        config: NeatConfig,
+    ) -> Result<Self, NeatError> {
         let mut core = Core::new();
 
+        config.validate()?;
+
+        if let Some(seed) = config.seed {
+            core.rng = ChaCha8Rng::seed_from_u64(seed);
+        }
+
+        core.config = config;
         core.population = population;
 
         match default_genome {
             Some(genome) => {
-                #[cfg(debug_assertions)]
-                {
-                    if genome.0.len() != genome.1.len() || genome.0.len() != genome.2.len() {
-                        panic!("Genome must have equal length vectors at neatcore");
-                    }
-                }
-
                 for _ in 0..population {
 
                     core.gen_arr.push((*genome).clone());
@@ -104,125 +99,321 @@ impl Core {
         }
 
         if levels.1 == 0 {
-            panic!("Cannot set levels.1 to 0 at neatcore");
+            return Err(NeatError::NoOutputs);
         }
 
         let level0_range = 1..(levels.0 + 1);
-        let level1_range = (levels.0 + 1)..(levels.1 + 2);
-        
-        #[cfg(debug_assertions)]
-        {
-            for output in level1_range.clone() {
-                if level0_range.contains(&output) {
-                    panic!("Output neuron cannot be in input layer at neatcore");
-                }
-            }
-        }
+        let level1_range = (levels.0 + 1)..(levels.0 + levels.1 + 1);
 
         core.output_set.extend(level0_range.clone());
         core.output_set.extend(level1_range.clone());
 
-        core.table.set_levels(level0_range.collect::<Vec<usize>>(), level1_range.collect::<Vec<usize>>());
+        core.table.set_levels(level0_range.collect::<Vec<usize>>(), level1_range.collect::<Vec<usize>>())?;
 
-        match innovations {
-            Some(innovations) => {
-                for innovation in innovations {
-                    core.table.add_innovation((innovation.0, innovation.1, Type::Connector));
-                }
-            },
-            None => (),
+        if let Some(innovations) = innovations {
+            for innovation in innovations {
+                core.table.add_innovation((innovation.0, innovation.1, Type::Connector))?;
+            }
         }
-    
 
-        core
+        Ok(core)
     }
 
-    fn run(&self, index: usize, inputs: Vec<f64>) -> Vec<f64> {
-        #[cfg(debug_assertions)]
-        {
-            if inputs.len() != self.table.neuron_levels.0.len() {
-                panic!("Input length does not match input layer length at neatcore");
-            }
+    pub fn run(&self, index: usize, inputs: Vec<f64>) -> Result<Vec<f64>, NeatError> {
+        let mut network = self.build_network(index)?;
+        Ok(network.run(inputs)?)
+    }
+
+    // Network of a genome with the activations from the config
+    fn build_network(&self, index: usize) -> Result<NeuralNetwork, NetworkError> {
+        let mut network = NeuralNetwork::init(&self.gen_arr[index], &self.table)?;
+        network.set_activations(self.config.hidden_activation, self.config.output_activation);
+
+        Ok(network)
+    }
+
+    fn mutate(&mut self, index: usize) -> Result<(), NeatError> {
+        let random_tup: [f64; 6] = self.rng.gen();
+
+        if random_tup[0] < self.config.add_connection_rate && !self.add_connection(index)? {
+            self.full_genomes += 1;
         }
 
-        let genome = &self.gen_arr[index];
+        if random_tup[1] < self.config.add_node_rate {
+            self.add_node(index)?;
+        }
 
-        let mut network = NeuralNetwork::init(genome, &self.table);
-        network.run(inputs)
+        if random_tup[2] < self.config.weight_change_rate {
+            self.mutate_weights(index);
+        }
+
+        if random_tup[3] < self.config.toggle_rate {
+            self.toggle_connector(index);
+        }
+
+        if random_tup[4] < self.config.delete_connection_rate {
+            self.delete_connection(index)?;
+        }
+
+        if random_tup[5] < self.config.delete_neuron_rate {
+            self.delete_neuron(index)?;
+        }
+
+        Ok(())
     }
 
-    fn mutate(&mut self, index: usize) {
-        let mut rng: rand::prelude::ThreadRng = rand::thread_rng();
-        let random_tup: (f64, f64, f64) = (rng.gen(), rng.gen(), rng.gen());
+    // Adds a connection the genome doesnt have yet, disabled genes count as present
+    // Random picks are retried add_connection_attempts times, which is cheap while the network is sparse,
+    // after that the exact list of absent connections is built
+    // Returns false if the network is already fully connected
+    fn add_connection(&mut self, index: usize) -> Result<bool, NeatError> {
+        let network = NeuralNetwork::init(&self.gen_arr[index], &self.table)?;
+        let all_connections = Self::get_all_connections(&network.layers, &network.neuron_levels, self.config.recurrent);
 
-        if random_tup.0 < ADD_CONN {
-            let chosen_connector = self.get_random_connector(index);
-            self.add_connector(index, chosen_connector);
+        if all_connections.is_empty() {
+            return Ok(false);
         }
 
-        if random_tup.1 < ADD_NODE {
-            let chosen_connector = self.get_random_connector(index);
-            let new_neuron = self.table.inc_neuron();
+        let mut chosen = None;
+
+        for _ in 0..self.config.add_connection_attempts {
+            let connection = all_connections[self.rng.gen_range(0..all_connections.len())];
 
-            self.add_connector(index, (chosen_connector.0, new_neuron));
-            self.add_connector(index, (new_neuron, chosen_connector.1));
+            if !self.has_connector(index, connection) {
+                chosen = Some(connection);
+                break;
+            }
         }
 
-        if random_tup.2 < CHNG_WEIGHT {
-            let weights = &mut self.gen_arr[index].1;
-            let len = weights.len();
+        let connection = match chosen {
+            Some(connection) => connection,
+            None => {
+                let absent: Vec<(usize, usize)> = all_connections.into_iter()
+                    .filter(|connection| !self.has_connector(index, *connection))
+                    .collect();
+
+                if absent.is_empty() {
+                    return Ok(false);
+                }
 
-            if len != 0 {
-                let change = rng.gen_range(WGHT_CHNG_RNG.1..WGHT_CHNG_RNG.0);
-                weights[rng.gen_range(0..len)] += change;
+                absent[self.rng.gen_range(0..absent.len())]
             }
+        };
+
+        let id = self.connector_innovation(connection)?;
+        let weight = self.config.clamp_weight(self.config.new_connection_weight.sample(&mut self.rng));
+        self.gen_arr[index].insert(Gene::new(id, weight, true));
+
+        Ok(true)
+    }
+
+    // Every enabled gene is either perturbed by a Gaussian, replaced with a fresh weight or left alone
+    fn mutate_weights(&mut self, index: usize) {
+        let config = &self.config;
+
+        for gene in self.gen_arr[index].iter_mut().filter(|gene| gene.enabled) {
+            let roll: f64 = self.rng.gen();
+
+            let weight = if roll < config.weight_replace_rate {
+                config.new_connection_weight.sample(&mut self.rng)
+            } else if roll < config.weight_replace_rate + config.weight_perturb_rate {
+                gene.weight + config.weight_perturb_sigma * gaussian(&mut self.rng)
+            } else {
+                continue;
+            };
+
+            gene.weight = config.clamp_weight(weight);
         }
     }
 
-    fn get_random_connector(&self, index: usize) -> (usize, usize) {
-        let genome = &self.gen_arr[index];
-        let network = NeuralNetwork::init(genome, &self.table);
+    // Removes a random connector gene, then any hidden neuron that lost its way in or out
+    fn delete_connection(&mut self, index: usize) -> Result<(), NeatError> {
+        let len = self.gen_arr[index].len();
 
-        let all_connections = Self::get_all_connections(&network.layers, &network.neuron_levels);
+        if len == 0 {
+            return Ok(());
+        }
 
-        let mut rng: rand::prelude::ThreadRng = rand::thread_rng();
+        let id = self.gen_arr[index].genes()[self.rng.gen_range(0..len)].innovation;
+        self.gen_arr[index].remove(id);
 
-        assert_ne!(all_connections.len(), 0, "All connections of a network are equal to 0. This should not be possible as bias neuron and output should always be able to connect");
-        all_connections[rng.gen_range(0..all_connections.len())]
+        self.prune_orphans(index)
     }
 
-    fn add_connector(&mut self, index: usize, connection: (usize, usize)) {
-        let genome = &mut self.gen_arr[index];
+    // Removes a random hidden neuron with every connector going into or out of it
+    // Inputs, outputs and the bias are never removed
+    fn delete_neuron(&mut self, index: usize) -> Result<(), NeatError> {
+        let mut hidden: Vec<usize> = Vec::new();
 
-        match self.table.get_innovation((connection.0, connection.1, Type::Connector)) {
-            Some(id) => {
-                match genome.0.iter().position(|x| x == id) {
-                    Some(innov_index) => {
-                        genome.2[innov_index] = !genome.2[innov_index];
-                    },
-                    None => {
-                        genome.0.push(*id);
-                        genome.1.push(0.0);
-                        genome.2.push(true);
+        for (from, to, _) in self.connections(index)? {
+            for neuron in [from, to] {
+                if self.is_hidden(neuron) && !hidden.contains(&neuron) {
+                    hidden.push(neuron);
+                }
+            }
+        }
+
+        if hidden.is_empty() {
+            return Ok(());
+        }
+
+        hidden.sort();
+        let neuron = hidden[self.rng.gen_range(0..hidden.len())];
+
+        for (from, to, id) in self.connections(index)? {
+            if from == neuron || to == neuron {
+                self.gen_arr[index].remove(id);
+            }
+        }
+
+        self.prune_orphans(index)
+    }
+
+    // Hidden neurons without a connector coming in or going out cant pass anything on, so they are dropped
+    // along with their remaining connectors, until none are left. Self loops dont count either way
+    fn prune_orphans(&mut self, index: usize) -> Result<(), NeatError> {
+        loop {
+            let connections = self.connections(index)?;
+            let mut orphans: HashSet<usize> = HashSet::new();
+
+            for (from, to, _) in connections.iter() {
+                for neuron in [*from, *to] {
+                    if !self.is_hidden(neuron) {
+                        continue;
                     }
-                } 
-            },
-            None => {
-                self.table.add_innovation((connection.0, connection.1, Type::Connector));
 
-                genome.0.push(self.table.innovations.len() - 1);
-                genome.1.push(0.0);
-                genome.2.push(true);
+                    let incoming = connections.iter().any(|(f, t, _)| *t == neuron && *f != neuron);
+                    let outgoing = connections.iter().any(|(f, t, _)| *f == neuron && *t != neuron);
+
+                    if !incoming || !outgoing {
+                        orphans.insert(neuron);
+                    }
+                }
+            }
+
+            if orphans.is_empty() {
+                return Ok(());
+            }
+
+            for (from, to, id) in connections {
+                if orphans.contains(&from) || orphans.contains(&to) {
+                    self.gen_arr[index].remove(id);
+                }
             }
         }
-     }
+    }
+
+    // (from, to, innovation id) of every connector gene in a genome
+    fn connections(&self, index: usize) -> Result<Vec<(usize, usize, usize)>, NetworkError> {
+        let mut connections = Vec::new();
 
-    fn get_all_connections(layers: &Layers, levels: &(Vec<usize>, Vec<usize>)) -> Vec<(usize, usize)> {
+        for gene in self.gen_arr[index].iter() {
+            let innovation = self.table.innovations.get(gene.innovation).ok_or(NetworkError::UnknownGene(gene.innovation))?;
+
+            if innovation.kind == Type::Connector {
+                connections.push((innovation.from, innovation.to, innovation.id));
+            }
+        }
+
+        Ok(connections)
+    }
+
+    fn is_hidden(&self, neuron: usize) -> bool {
+        neuron != 0 && !self.table.neuron_levels.0.contains(&neuron) && !self.table.neuron_levels.1.contains(&neuron)
+    }
+
+    // Flips the enabled flag of a random gene
+    fn toggle_connector(&mut self, index: usize) {
+        let len = self.gen_arr[index].len();
+
+        if len == 0 {
+            return;
+        }
+
+        let gene = self.rng.gen_range(0..len);
+
+        if let Some(gene) = self.gen_arr[index].iter_mut().nth(gene) {
+            gene.enabled = !gene.enabled;
+        }
+    }
+
+    // Splits a random enabled connector gene with a new neuron, the old gene is disabled
+    // The neuron gets an incoming weight of 1.0 and passes on the old weight, so the network barely changes
+    // (exactly the same with an Identity hidden activation)
+    fn add_node(&mut self, index: usize) -> Result<(), NeatError> {
+        // Unknown genes are kept so the lookup below reports them
+        let enabled: Vec<usize> = self.gen_arr[index].iter()
+            .filter(|gene| gene.enabled)
+            .filter(|gene| self.table.innovations.get(gene.innovation).is_none_or(|innovation| innovation.kind == Type::Connector))
+            .map(|gene| gene.innovation)
+            .collect();
+
+        // Nothing to split yet
+        if enabled.is_empty() {
+            return Ok(());
+        }
+
+        let id = enabled[self.rng.gen_range(0..enabled.len())];
+        let innovation = self.table.innovations.get(id).ok_or(NetworkError::UnknownGene(id))?;
+        let (from, to) = (innovation.from, innovation.to);
+
+        // A neuron this genome already has, from the same split or from anything else, cant be reused
+        // it would wire the split into unrelated connectors and could even close a cycle
+        let used: HashSet<usize> = self.connections(index)?.into_iter()
+            .flat_map(|(from, to, _)| [from, to])
+            .collect();
+
+        let new_neuron = self.table.split_connection(from, to, |neuron| used.contains(&neuron))?;
+
+        let genome = &mut self.gen_arr[index];
+        let old_weight = genome.find_weight(id).unwrap_or(0.0);
+        genome.disable(id);
+
+        let incoming = self.connector_innovation((from, new_neuron))?;
+        let outgoing = self.connector_innovation((new_neuron, to))?;
+
+        let genome = &mut self.gen_arr[index];
+        genome.insert(Gene::new(incoming, 1.0, true));
+        genome.insert(Gene::new(outgoing, old_weight, true));
+
+        Ok(())
+    }
+
+    // Innovation id of a connection, added to the table if no genome had it before
+    fn connector_innovation(&mut self, connection: (usize, usize)) -> Result<usize, InnovationError> {
+        match self.table.get_innovation((connection.0, connection.1, Type::Connector)) {
+            Some(id) => Ok(*id),
+            None => self.table.add_innovation((connection.0, connection.1, Type::Connector)),
+        }
+    }
+
+    fn has_connector(&self, index: usize, connection: (usize, usize)) -> bool {
+        self.table.get_innovation((connection.0, connection.1, Type::Connector))
+            .is_some_and(|id| self.gen_arr[index].contains(*id))
+    }
+
+    // Feedforward connections only go to a later layer and never start at an output
+    // In recurrent mode any neuron can connect to any non input neuron, itself included
+    fn get_all_connections(layers: &Layers, levels: &(Vec<usize>, Vec<usize>), recurrent: bool) -> Vec<(usize, usize)> {
         let mut possible_connections: Vec<(usize, usize)> = Vec::new();
 
         let input_hash: HashSet<usize> = levels.0.iter().copied().collect();
         let output_hash: HashSet<usize> = levels.1.iter().copied().collect();
 
+        if recurrent {
+            for from_neuron in layers.iter().flatten() {
+                for to_neuron in layers.iter().flatten() {
+                    if *to_neuron == 0 || input_hash.contains(to_neuron) {
+                        continue;
+                    }
+
+                    possible_connections.push((*from_neuron, *to_neuron));
+                }
+            }
+
+            return possible_connections;
+        }
+
         for (i, layer) in layers.iter().enumerate() {
             for from_neuron in layer {
                 if output_hash.contains(from_neuron) {
@@ -244,8 +435,8 @@ impl Core {
         possible_connections
     }
 
-    pub fn to_json(&self, index: usize, path: &str) {
-        let network = NeuralNetwork::init(&self.gen_arr[index], &self.table);
+    pub fn to_json(&self, index: usize, path: &str) -> Result<(), NeatError> {
+        let network = NeuralNetwork::init(&self.gen_arr[index], &self.table)?;
 
         let mut nodes: Vec<usize> = Vec::new();
         let mut connections: Vec<(usize, usize)> = Vec::new();
@@ -258,55 +449,416 @@ impl Core {
             connections.push((connection.from, connection.to));
         }
 
-        let json_net = JSON_network {
+        let json_net = JsonNetwork {
             nodes,
             connections,
             layers: network.layers,
         };
 
-        let serialized = serde_json::to_string(&json_net).unwrap();
-        std::fs::write(path, serialized).unwrap();
+        let serialized = serde_json::to_string(&json_net)?;
+        std::fs::write(path, serialized)?;
+
+        Ok(())
     }
 
-    fn backprop(&mut self, index: usize, breadth: usize, range: usize, itterations: usize) {
-        let network = NeuralNetwork::init(&self.gen_arr[index], &self.table);
+    // Graphviz source of a genome, see NeuralNetwork::to_dot
+    pub fn to_dot(&self, index: usize, path: &str) -> Result<(), NeatError> {
+        let network = NeuralNetwork::init(&self.gen_arr[index], &self.table)?;
+        std::fs::write(path, network.to_dot())?;
+
+        Ok(())
+    }
 
-        let inc = range as f64 / breadth as f64;
+    // Standalone svg of a genome, no graphviz needed
+    pub fn to_svg(&self, index: usize, path: &str) -> Result<(), NeatError> {
+        let network = NeuralNetwork::init(&self.gen_arr[index], &self.table)?;
+        std::fs::write(path, network.to_svg())?;
+
+        Ok(())
+    }
+
+    // Png of a genome, laid out by layer like to_svg
+    pub fn render_png(&self, index: usize, path: &str) -> Result<(), NeatError> {
+        let network = NeuralNetwork::init(&self.gen_arr[index], &self.table)?;
+        std::fs::write(path, network.to_png()?)?;
+
+        Ok(())
+    }
+
+    // Snapshot of a genome that can be rebuilt without the rest of the population
+    pub fn saved_network(&self, index: usize) -> SavedNetwork {
+        SavedNetwork {
+            genome: self.gen_arr[index].clone(),
+            table: self.table.clone(),
+            hidden_activation: self.config.hidden_activation,
+            output_activation: self.config.output_activation,
+        }
+    }
+
+    // Weights, enabled flags and innovation ids are all kept, load it back with SavedNetwork::load
+    pub fn save(&self, index: usize, path: &str) -> Result<(), NeatError> {
+        self.saved_network(index).save(path)
+    }
+
+    // Fine tunes the weights of a genome with gradient descent over (inputs, targets) samples
+    // Weights are written back into the genome, disabled genes are left alone
+    // Returns the mean loss of the last itteration
+    pub fn backprop(&mut self, index: usize, samples: &[(Vec<f64>, Vec<f64>)], itterations: usize) -> Result<f64, NeatError> {
+        if samples.is_empty() {
+            return Err(NeatError::NoSamples);
+        }
+
+        let mut network = self.build_network(index)?;
+        let mut loss = f64::NAN;
 
-        log(&network.layers);
-        
         for _ in 0..itterations {
-            for b in (breadth as i32 * -1)..(breadth as i32) {
-                let input = inc * b as f64;
-    
-                
+            loss = 0.0;
+
+            for (inputs, targets) in samples {
+                // Samples are independent, so recurrent memory shouldnt carry over
+                network.reset();
+
+                let (gradients, sample_loss) = network.gradients(inputs.clone(), targets)?;
+                network.apply_gradients(&gradients, self.config.learning_rate);
+
+                loss += sample_loss;
+            }
+
+            loss /= samples.len() as f64;
+
+            if !loss.is_finite() {
+                return Err(NeatError::Diverged);
             }
         }
+
+        // Weights are only written back once they are all finite, so a diverging run cant poison the genome
+        if network.connectors.iter().any(|connector| !connector.weight.is_finite()) {
+            return Err(NeatError::Diverged);
+        }
+
+        let genome = &mut self.gen_arr[index];
+
+        for connector in network.connectors.iter() {
+            if let Some(gene) = genome.get_mut(connector.id) {
+                if gene.enabled {
+                    gene.weight = connector.weight;
+                }
+            }
+        }
+
+        Ok(loss)
+    }
+
+    fn evaluate<F>(&mut self, fitness: &mut F) -> Result<(), NeatError> where F: FnMut(&mut NeuralNetwork) -> f64 {
+        for i in 0..self.gen_arr.len() {
+            let mut network = self.build_network(i)?;
+            self.fit_arr[i] = fitness(&mut network);
+        }
+
+        self.replace_nan();
+        self.update_champion();
+
+        Ok(())
     }
 
-    
+    // Splits the population into config.threads contiguous chunks, one scoped thread each
+    // Every genome writes to its own slot in fit_arr, so the result doesnt depend on thread timing
+    fn evaluate_parallel<F>(&mut self, fitness: &F) -> Result<(), NeatError> where F: Fn(&mut NeuralNetwork) -> f64 + Sync {
+        let chunk_size = self.gen_arr.len().div_ceil(self.config.threads).max(1);
+        let mut fit_arr = std::mem::take(&mut self.fit_arr);
 
-    pub fn train(&mut self) {
-        // mutate population
-        self.table.add_innovation((1, 2, Type::Connector));
+        let core = &*self;
 
-        self.gen_arr[0].0 = vec![0];
-        self.gen_arr[0].1 = vec![0.0];
-        self.gen_arr[0].2 = vec![true];
+        let result = thread::scope(|scope| {
+            let handles: Vec<_> = fit_arr.chunks_mut(chunk_size).enumerate()
+                .map(|(chunk, fits)| scope.spawn(move || -> Result<(), NetworkError> {
+                    for (i, fit) in fits.iter_mut().enumerate() {
+                        let mut network = core.build_network(chunk * chunk_size + i)?;
+                        *fit = fitness(&mut network);
+                    }
 
-        let mut timer = Timer::new();
-        timer.start();
+                    Ok(())
+                }))
+                .collect();
 
-        self.backprop(0, 10, 5, 10);
+            // Joined in order, so the first error is always the same one
+            handles.into_iter()
+                .try_for_each(|handle| handle.join().expect("Fitness function panicked at neatcore"))
+        });
 
-        timer.stop();
-        timer.log();
+        self.fit_arr = fit_arr;
+        result?;
 
+        self.replace_nan();
+        self.update_champion();
 
-        //self.to_json(0, "../render/network.json");
+        Ok(())
     }
-}
 
-fn log<T>(var: &T) where T: std::fmt::Debug {
-    println!("{:?}", var);
-}
\ No newline at end of file
+    // total_cmp ranks NaN above every number, so a NaN fitness would win selection and freeze the champion
+    // It counts as the worst finite fitness of the generation instead, -inf only if there is none
+    fn replace_nan(&mut self) {
+        let worst = self.fit_arr.iter().copied().filter(|fitness| fitness.is_finite()).fold(f64::INFINITY, f64::min);
+        let worst = if worst.is_finite() { worst } else { f64::NEG_INFINITY };
+
+        for fitness in self.fit_arr.iter_mut().filter(|fitness| fitness.is_nan()) {
+            *fitness = worst;
+        }
+    }
+
+    fn update_champion(&mut self) {
+        let best = (0..self.fit_arr.len())
+            .max_by(|a, b| self.fit_arr[*a].total_cmp(&self.fit_arr[*b]));
+
+        if let Some(best) = best {
+            let improved = match &self.champion {
+                Some((_, fitness)) => self.fit_arr[best] > *fitness,
+                None => true,
+            };
+
+            if improved {
+                self.champion = Some((self.gen_arr[best].clone(), self.fit_arr[best]));
+            }
+        }
+    }
+
+    // Puts every genome into the first species whose exemplar is close enough, or starts a new species
+    fn speciate(&mut self) {
+        for species in self.species.iter_mut() {
+            species.members.clear();
+        }
+
+        for i in 0..self.gen_arr.len() {
+            let genome = &self.gen_arr[i];
+
+            match self.species.iter_mut().find(|species| distance(&species.exemplar, genome, &self.config) < self.config.compat_threshold) {
+                Some(species) => species.members.push(i),
+                None => {
+                    let mut species = Species::init(genome.clone());
+                    species.members.push(i);
+
+                    self.species.push(species);
+                }
+            }
+        }
+
+        self.species.retain(|species| !species.members.is_empty());
+
+        // A random member represents the species when comparing the next generation
+        for species in self.species.iter_mut() {
+            let exemplar = species.members[self.rng.gen_range(0..species.members.len())];
+            species.exemplar = self.gen_arr[exemplar].clone();
+        }
+    }
+
+    // Truncation selection, the best survival_rate of each species gets to reproduce
+    fn select(&self) -> Vec<Vec<usize>> {
+        let mut parents: Vec<Vec<usize>> = Vec::new();
+
+        for species in self.species.iter() {
+            let mut ranked = species.members.clone();
+            ranked.sort_by(|a, b| self.fit_arr[*b].total_cmp(&self.fit_arr[*a]));
+
+            let survivors = ((ranked.len() as f64 * self.config.survival_rate).ceil() as usize).max(1);
+            ranked.truncate(survivors);
+
+            parents.push(ranked);
+        }
+
+        parents
+    }
+
+    // Index of the species holding the champion, or the species closest to it if the champion isnt a member anymore
+    fn champion_species(&self) -> Option<usize> {
+        self.champion.as_ref().and_then(|(champion, _)| {
+            self.species.iter()
+                .position(|species| species.members.iter().any(|member| self.gen_arr[*member] == *champion))
+                .or_else(|| (0..self.species.len()).min_by(|a, b| {
+                    distance(&self.species[*a].exemplar, champion, &self.config)
+                        .total_cmp(&distance(&self.species[*b].exemplar, champion, &self.config))
+                }))
+        })
+    }
+
+    // Species that havent improved in stagnation_limit generations stop reproducing
+    // The species holding the champion, the best genome ever evaluated, is always kept
+    // If the champion isnt in the population anymore, the species closest to it is kept instead
+    fn cull_stagnant(&mut self) {
+        for species in self.species.iter_mut() {
+            species.update_stagnation(&self.fit_arr);
+        }
+
+        let protected = self.champion_species();
+        let limit = self.config.stagnation_limit;
+
+        self.species = std::mem::take(&mut self.species).into_iter()
+            .enumerate()
+            .filter(|(i, species)| species.stagnant_generations <= limit || Some(*i) == protected)
+            .map(|(_, species)| species)
+            .collect();
+    }
+
+    fn share_fitness(&mut self) {
+        for species in self.species.iter_mut() {
+            species.fitness = shared_fitness(&species.members, &self.fit_arr);
+        }
+    }
+
+    fn allocate_offspring(&self) -> Vec<usize> {
+        let species_fitness: Vec<f64> = self.species.iter().map(|species| species.fitness).collect();
+        let sizes: Vec<usize> = self.species.iter().map(|species| species.members.len()).collect();
+        let min = self.fit_arr.iter().copied().filter(|fitness| fitness.is_finite()).fold(f64::INFINITY, f64::min);
+
+        allocate_offspring(&species_fitness, &sizes, min, self.population)
+    }
+
+    // Each species gets its quota of children, and children only come from parents in the same species
+    // Large enough species first copy their elitism best genomes unchanged, the rest of the children are
+    // either a crossover of two parents or a clone of one, and get mutated
+    fn reproduce(&mut self, parents: Vec<Vec<usize>>, quotas: Vec<usize>) -> Result<(), NeatError> {
+        let mut children: Vec<Genome> = Vec::new();
+        let mut elites: Vec<bool> = Vec::new();
+
+        let mut ranges: Vec<std::ops::Range<usize>> = Vec::new();
+
+        for ((species, species_parents), quota) in self.species.iter().zip(parents.iter()).zip(quotas.iter()) {
+            let start = children.len();
+
+            let elite_count = if species.members.len() >= self.config.elitism_min_size {
+                self.config.elitism.min(*quota).min(species_parents.len())
+            } else {
+                0
+            };
+
+            // Parents are ranked, so the first ones are the best
+            for elite in species_parents.iter().take(elite_count) {
+                children.push(self.gen_arr[*elite].clone());
+                elites.push(true);
+            }
+
+            for _ in elite_count..*quota {
+                let parent_1 = species_parents[self.rng.gen_range(0..species_parents.len())];
+
+                if species_parents.len() > 1 && self.rng.gen::<f64>() < self.config.crossover_rate {
+                    let parent_2 = species_parents[self.rng.gen_range(0..species_parents.len())];
+
+                    children.push(crossover(
+                        &self.gen_arr[parent_1], self.fit_arr[parent_1],
+                        &self.gen_arr[parent_2], self.fit_arr[parent_2],
+                        &self.config,
+                        &mut self.rng,
+                    ));
+                } else {
+                    children.push(self.gen_arr[parent_1].clone());
+                }
+
+                elites.push(false);
+            }
+
+            ranges.push(start..children.len());
+        }
+
+        // The champion is always carried over unchanged, even when its species is too small for elites or got no children
+        // It takes the place of a child that would be mutated, from its own species if possible
+        if let Some((champion, _)) = &self.champion {
+            let carried = children.iter().zip(elites.iter()).any(|(child, elite)| *elite && child == champion);
+
+            if !carried {
+                let own = self.champion_species().map(|species| ranges[species].clone()).unwrap_or(0..0);
+
+                let slot = own.into_iter().find(|i| !elites[*i])
+                    .or_else(|| (0..children.len()).find(|i| !elites[*i]))
+                    .or(children.len().checked_sub(1));
+
+                if let Some(slot) = slot {
+                    children[slot] = champion.clone();
+                    elites[slot] = true;
+                }
+            }
+        }
+
+        self.gen_arr = children;
+        self.fit_arr = vec![0.0; self.population];
+
+        if self.config.splits_per_generation {
+            self.table.clear_splits();
+        }
+
+        self.full_genomes = 0;
+
+        for (i, elite) in elites.into_iter().enumerate() {
+            if !elite {
+                self.mutate(i)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Everything after evaluation: speciate, cull stagnant species, share fitness, select, reproduce and mutate
+    // Returns the best fitness of the evaluated generation
+    fn next_generation(&mut self) -> Result<f64, NeatError> {
+        self.speciate();
+        self.cull_stagnant();
+
+        let best = self.fit_arr.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+        self.share_fitness();
+
+        let parents = self.select();
+        let quotas = self.allocate_offspring();
+        self.reproduce(parents, quotas)?;
+
+        self.generation += 1;
+        self.periodic_checkpoint()?;
+
+        Ok(best)
+    }
+
+    // Runs a single generation, returns the best fitness of the evaluated generation
+    pub fn evolve<F>(&mut self, mut fitness: F) -> Result<f64, NeatError> where F: FnMut(&mut NeuralNetwork) -> f64 {
+        self.evaluate(&mut fitness)?;
+        self.next_generation()
+    }
+
+    // Same as evolve, but genomes are evaluated on config.threads threads
+    // Gives the same population as evolve for the same seed
+    pub fn evolve_parallel<F>(&mut self, fitness: F) -> Result<f64, NeatError> where F: Fn(&mut NeuralNetwork) -> f64 + Sync {
+        self.evaluate_parallel(&fitness)?;
+        self.next_generation()
+    }
+
+    pub fn train<F>(&mut self, generations: usize, mut fitness: F) -> Result<(), NeatError> where F: FnMut(&mut NeuralNetwork) -> f64 {
+        for _ in 0..generations {
+            self.evolve(&mut fitness)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn train_parallel<F>(&mut self, generations: usize, fitness: F) -> Result<(), NeatError> where F: Fn(&mut NeuralNetwork) -> f64 + Sync {
+        for _ in 0..generations {
+            self.evolve_parallel(&fitness)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    pub fn species_count(&self) -> usize {
+        self.species.len()
+    }
+
+    // How many genomes tried to add a connection in the last generation but were already fully connected
+    pub fn full_genomes(&self) -> usize {
+        self.full_genomes
+    }
+
+    // Best genome found so far and its fitness
+    pub fn champion(&self) -> Option<(&Genome, f64)> {
+        self.champion.as_ref().map(|(genome, fitness)| (genome, *fitness))
+    }
+}
@@ -0,0 +1,53 @@
+use crate::NeatError;
+use network::{Activation, Genome, NeuralNetwork, NetworkError};
+use innovation::InnovationTable;
+use serde::{Deserialize, Serialize};
+
+use std::path::Path;
+
+// Everything needed to rebuild an evolved network in another process
+// The table is saved whole, so innovation ids in the genome keep pointing at the same connectors
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SavedNetwork {
+    pub genome: Genome,
+    pub table: InnovationTable,
+    pub hidden_activation: Activation,
+    pub output_activation: Activation,
+}
+
+impl SavedNetwork {
+    pub fn network(&self) -> Result<NeuralNetwork, NetworkError> {
+        let mut network = NeuralNetwork::init(&self.genome, &self.table)?;
+        network.set_activations(self.hidden_activation, self.output_activation);
+
+        Ok(network)
+    }
+
+    pub fn to_json(&self) -> Result<String, NeatError> {
+        check_weights(&self.genome)?;
+
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, NeatError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn save<P>(&self, path: P) -> Result<(), NeatError> where P: AsRef<Path> {
+        std::fs::write(path, self.to_json()?)?;
+
+        Ok(())
+    }
+
+    pub fn load<P>(path: P) -> Result<Self, NeatError> where P: AsRef<Path> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+}
+
+// serde_json writes NaN and inf as null, which then fails to load, so genomes holding one are refused instead
+pub(crate) fn check_weights(genome: &Genome) -> Result<(), NeatError> {
+    match genome.iter().find(|gene| !gene.weight.is_finite()) {
+        Some(gene) => Err(NeatError::NonFiniteWeight(gene.innovation)),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,126 @@
+use crate::NeatConfig;
+use network::Genome;
+use serde::{Serialize, Deserialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct Species {
+    pub exemplar: Genome,
+    pub members: Vec<usize>,
+    pub stagnant_generations: usize,
+    #[serde(with = "crate::checkpoint::float")]
+    pub fitness: f64,
+    #[serde(with = "crate::checkpoint::float")]
+    pub best_fitness: f64, // Best raw fitness any member has ever had
+}
+
+impl Species {
+    pub fn init(exemplar: Genome,) -> Self {
+        Self {
+            exemplar,
+            members: Vec::new(),
+            stagnant_generations: 0,
+            fitness: f64::NAN,
+            best_fitness: f64::NEG_INFINITY,
+        }
+    }
+
+    // Resets the stagnation counter if a member beat the best fitness so far, otherwise increments it
+    pub fn update_stagnation(&mut self, fit_arr: &[f64]) {
+        let best = self.members.iter()
+            .map(|member| fit_arr[*member])
+            .fold(f64::NEG_INFINITY, f64::max);
+
+        if best > self.best_fitness {
+            self.best_fitness = best;
+            self.stagnant_generations = 0;
+        } else {
+            self.stagnant_generations += 1;
+        }
+    }
+}
+
+// Compatibility distance from the NEAT paper
+// d = c1 * E / N + c2 * D / N + c3 * W
+// E = excess genes, D = disjoint genes, W = average weight difference of matching genes
+pub fn distance(genome_1: &Genome, genome_2: &Genome, config: &NeatConfig) -> f64 {
+    let max_1 = genome_1.max_innovation();
+    let max_2 = genome_2.max_innovation();
+
+    let mut excess: usize = 0;
+    let mut disjoint: usize = 0;
+    let mut matching: usize = 0;
+    let mut weight_diff: f64 = 0.0;
+
+    for gene in genome_1.iter() {
+        match genome_2.get(gene.innovation) {
+            Some(other) => {
+                matching += 1;
+                weight_diff += (gene.weight - other.weight).abs();
+            },
+            None if Some(gene.innovation) > max_2 => excess += 1,
+            None => disjoint += 1,
+        }
+    }
+
+    for gene in genome_2.iter() {
+        if genome_1.contains(gene.innovation) {
+            continue;
+        }
+
+        if Some(gene.innovation) > max_1 {
+            excess += 1;
+        } else {
+            disjoint += 1;
+        }
+    }
+
+    // Small genomes arent normalized, as in the paper
+    let len = genome_1.len().max(genome_2.len());
+    let n = if len < 20 { 1.0 } else { len as f64 };
+
+    let avg_weight_diff = if matching == 0 { 0.0 } else { weight_diff / matching as f64 };
+
+    config.c1 * excess as f64 / n + config.c2 * disjoint as f64 / n + config.c3 * avg_weight_diff
+}
+
+// Explicit fitness sharing, every member's fitness is divided by the size of its species
+// The species fitness is the sum of its adjusted fitnesses
+pub fn shared_fitness(members: &[usize], fit_arr: &[f64]) -> f64 {
+    let size = members.len() as f64;
+
+    members.iter()
+        .map(|member| fit_arr[*member] / size)
+        .sum()
+}
+
+// Offspring are allocated proportionally to species fitness, so the population stays the same size
+// Fitness can be negative, so it is shifted by the worst finite genome fitness (min_fitness) first
+// A species with a non finite fitness, from a member scoring -inf, counts as the worst instead of breaking the proportions
+pub fn allocate_offspring(species_fitness: &[f64], sizes: &[usize], min_fitness: f64, population: usize) -> Vec<usize> {
+    let shifted: Vec<f64> = species_fitness.iter()
+        .map(|fitness| if fitness.is_finite() { fitness - min_fitness } else { 0.0 })
+        .collect();
+
+    let total: f64 = shifted.iter().sum();
+
+    // Every species is equally bad, so fall back to their sizes
+    let shares: Vec<f64> = if total > 0.0 && total.is_finite() {
+        shifted.iter().map(|fitness| fitness / total * population as f64).collect()
+    } else {
+        sizes.iter().map(|size| *size as f64).collect()
+    };
+
+    let mut quotas: Vec<usize> = shares.iter().map(|share| share.floor() as usize).collect();
+
+    // Rounding remainders go to the species with the largest fractional parts
+    let mut remainders: Vec<usize> = (0..shares.len()).collect();
+    remainders.sort_by(|a, b| (shares[*b] - shares[*b].floor()).total_cmp(&(shares[*a] - shares[*a].floor())));
+
+    let allocated: usize = quotas.iter().sum();
+
+    for i in remainders.into_iter().cycle().take(population.saturating_sub(allocated)) {
+        quotas[i] += 1;
+    }
+
+    quotas
+}
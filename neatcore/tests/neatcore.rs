@@ -0,0 +1,698 @@
+use neatcore::{Core, NeatConfig, NeatError, SavedNetwork, WeightDistribution};
+use network::{Gene, Genome, NetworkError};
+
+use std::collections::HashSet;
+
+#[test]
+fn evolve() {
+    let mut core = Core::init(20, None, None, (2, 1), NeatConfig::default()).unwrap();
+
+    let mut last_best = f64::NEG_INFINITY;
+
+    for _ in 0..10 {
+        let best = core.evolve(|network| {
+            let output = network.run(vec![1.0, 2.0]).unwrap();
+            assert_eq!(output.len(), 1);
+
+            -(output[0] - 3.0).abs()
+        }).unwrap();
+
+        assert!(best.is_finite());
+        assert!(core.species_count() >= 1);
+        last_best = best;
+    }
+
+    assert_eq!(core.generation(), 10);
+    assert!(last_best <= 0.0);
+
+    // The champion is never worse than the last generation
+    let (_, champion_fitness) = core.champion().unwrap();
+    assert!(champion_fitness >= last_best);
+}
+
+#[test]
+fn crossover() {
+    let mut rng = rand::thread_rng();
+
+    let fitter: Genome = vec![Gene::new(0, 0.1, true), Gene::new(1, 0.2, true), Gene::new(3, 0.3, true), Gene::new(5, 0.4, false)].into_iter().collect();
+    let weaker: Genome = vec![Gene::new(4, 1.1, true), Gene::new(1, 1.2, true), Gene::new(0, 1.3, true), Gene::new(2, 1.4, true)].into_iter().collect();
+
+    for _ in 0..20 {
+        let child = neatcore::crossover(&weaker, 1.0, &fitter, 2.0, &NeatConfig::default(), &mut rng);
+
+        // Topology always comes from the fitter parent, in innovation order
+        assert_eq!(child.iter().map(|gene| gene.innovation).collect::<Vec<usize>>(), vec![0, 1, 3, 5]);
+
+        let weights: Vec<f64> = child.iter().map(|gene| gene.weight).collect();
+
+        assert!(weights[0] == 0.1 || weights[0] == 1.3);
+        assert!(weights[1] == 0.2 || weights[1] == 1.2);
+        assert_eq!(child.get(3), Some(&Gene::new(3, 0.3, true)));
+        assert_eq!(child.get(5), Some(&Gene::new(5, 0.4, false)));
+    }
+}
+
+#[test]
+fn config() {
+    let config = NeatConfig::from_toml("add_node_rate = 0.5\ncompat_threshold = 2.0").unwrap();
+
+    assert_eq!(config.add_node_rate, 0.5);
+    assert_eq!(config.compat_threshold, 2.0);
+    assert_eq!(config.c1, NeatConfig::default().c1);
+
+    let config = NeatConfig::from_json("{\"survival_rate\": 0.25}").unwrap();
+    assert_eq!(config.survival_rate, 0.25);
+
+    assert!(NeatConfig::from_json("{\"crossover_rate\": 1.5}").is_err());
+    assert!(NeatConfig::from_toml("weight_range = [1.0, -1.0]").is_err());
+}
+
+#[test]
+fn seeded() {
+    let config = NeatConfig { seed: Some(42), add_node_rate: 0.3, add_connection_rate: 0.5, ..NeatConfig::default() };
+
+    let run = || {
+        let mut core = Core::init(30, None, None, (2, 1), config.clone()).unwrap();
+        let mut history: Vec<f64> = Vec::new();
+
+        for _ in 0..15 {
+            history.push(core.evolve(|network| {
+                let output = network.run(vec![0.5, -1.0]).unwrap();
+                -(output[0] - 2.0).abs()
+            }).unwrap());
+        }
+
+        (history, core.champion().unwrap().0.clone())
+    };
+
+    let (history_1, champion_1) = run();
+    let (history_2, champion_2) = run();
+
+    assert_eq!(history_1, history_2);
+    assert_eq!(champion_1, champion_2);
+}
+
+#[test]
+fn recurrent() {
+    let config = NeatConfig { seed: Some(7), recurrent: true, add_node_rate: 0.3, add_connection_rate: 0.8, ..NeatConfig::default() };
+    let mut core = Core::init(30, None, None, (1, 1), config).unwrap();
+
+    // Output the previous input, which needs memory
+    for _ in 0..20 {
+        core.evolve(|network| {
+            let mut error = 0.0;
+            let mut previous = 0.0;
+
+            for input in [1.0, 0.0, 0.5, 1.0, 0.0] {
+                error += (network.run(vec![input]).unwrap()[0] - previous).abs();
+                previous = input;
+            }
+
+            -error
+        }).unwrap();
+    }
+
+    assert!(core.champion().is_some());
+
+    // Feedforward connections never start at the output or loop back to their own neuron, so these only come from recurrent mode
+    let recurrent_edge = |core: &Core, i: usize| {
+        let saved = core.saved_network(i);
+
+        saved.genome.iter().any(|gene| {
+            let innovation = &saved.table.innovations[gene.innovation];
+            innovation.from == innovation.to || innovation.from == 2
+        })
+    };
+
+    assert!((0..30).any(|i| recurrent_edge(&core, i)));
+
+    let config = NeatConfig { seed: Some(7), add_node_rate: 0.3, add_connection_rate: 0.8, ..NeatConfig::default() };
+    let mut core = Core::init(30, None, None, (1, 1), config).unwrap();
+    core.train(20, |network| network.run(vec![1.0]).unwrap()[0]).unwrap();
+
+    assert!((0..30).all(|i| !recurrent_edge(&core, i)));
+}
+
+#[test]
+fn backprop() {
+    let config = NeatConfig { learning_rate: 0.05, ..NeatConfig::default() };
+    let genome: Genome = vec![Gene::new(0, 0.0, true), Gene::new(1, 0.0, true)].into_iter().collect();
+
+    // Input to output and bias to output
+    let mut core = Core::init(1, Some(&genome), Some(vec![(1, 2), (0, 2)]), (1, 1), config).unwrap();
+
+    // y = 2x - 1
+    let samples: Vec<(Vec<f64>, Vec<f64>)> = (-5..=5)
+        .map(|i| (vec![i as f64 / 5.0], vec![2.0 * i as f64 / 5.0 - 1.0]))
+        .collect();
+
+    let first_loss = core.backprop(0, &samples, 1).unwrap();
+    let last_loss = core.backprop(0, &samples, 200).unwrap();
+
+    assert!(last_loss < first_loss);
+    assert!(last_loss < 1e-6);
+
+    assert!(matches!(core.backprop(0, &[], 10), Err(NeatError::NoSamples)));
+
+    // A huge learning rate blows the weights up, the genome keeps its last good weights
+    let config = NeatConfig { learning_rate: 1e6, ..NeatConfig::default() };
+    let mut core = Core::init(1, Some(&genome), Some(vec![(1, 2), (0, 2)]), (1, 1), config).unwrap();
+
+    assert!(matches!(core.backprop(0, &samples, 50), Err(NeatError::Diverged)));
+    assert_eq!(core.saved_network(0).genome, genome);
+}
+
+#[test]
+fn parallel() {
+    let fitness = |network: &mut network::NeuralNetwork| {
+        let output = network.run(vec![0.25, 0.75]).unwrap();
+        -(output[0] - 1.0).abs()
+    };
+
+    let serial_config = NeatConfig { seed: Some(3), add_node_rate: 0.2, add_connection_rate: 0.5, ..NeatConfig::default() };
+    let parallel_config = NeatConfig { threads: 4, ..serial_config.clone() };
+
+    let mut serial = Core::init(25, None, None, (2, 1), serial_config).unwrap();
+    let mut parallel = Core::init(25, None, None, (2, 1), parallel_config).unwrap();
+
+    for _ in 0..10 {
+        assert_eq!(serial.evolve(fitness).unwrap(), parallel.evolve_parallel(fitness).unwrap());
+    }
+
+    assert_eq!(serial.champion().unwrap().0, parallel.champion().unwrap().0);
+}
+
+#[test]
+fn errors() {
+    assert!(matches!(Core::init(10, None, None, (2, 0), NeatConfig::default()), Err(NeatError::NoOutputs)));
+
+    let config = NeatConfig { threads: 0, ..NeatConfig::default() };
+    assert!(matches!(Core::init(10, None, None, (1, 1), config), Err(NeatError::Config(_))));
+
+    // Gene 3 was never added to the innovation table
+    let genome: Genome = vec![Gene::new(3, 1.0, true)].into_iter().collect();
+    let core = Core::init(10, Some(&genome), None, (1, 1), NeatConfig::default()).unwrap();
+
+    assert!(matches!(core.run(0, vec![1.0]), Err(NeatError::Network(NetworkError::UnknownGene(3)))));
+}
+
+#[test]
+fn save_load() {
+    let config = NeatConfig { seed: Some(11), add_node_rate: 0.3, add_connection_rate: 0.6, ..NeatConfig::default() };
+    let mut core = Core::init(20, None, None, (2, 1), config).unwrap();
+
+    for _ in 0..15 {
+        core.evolve(|network| {
+            let output = network.run(vec![0.5, -0.5]).unwrap();
+            -(output[0] - 0.3).abs()
+        }).unwrap();
+    }
+
+    let path = std::env::temp_dir().join("neatcore_save_load.json");
+    core.save(0, path.to_str().unwrap()).unwrap();
+
+    let loaded = SavedNetwork::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // Weights, flags, ids and the table all survive exactly
+    assert_eq!(loaded, core.saved_network(0));
+
+    let mut network = loaded.network().unwrap();
+
+    for inputs in [vec![0.5, -0.5], vec![1.0, 2.0], vec![-3.0, 0.25]] {
+        assert_eq!(network.run(inputs.clone()).unwrap(), core.run(0, inputs).unwrap());
+    }
+
+    // A NaN weight couldnt be loaded back, so it isnt saved at all
+    let mut diverged = loaded.clone();
+    let innovation = diverged.genome.genes()[0].innovation;
+    diverged.genome.insert(Gene::new(innovation, f64::NAN, true));
+
+    assert!(matches!(diverged.save(&path), Err(NeatError::NonFiniteWeight(id)) if id == innovation));
+    assert!(!path.exists());
+}
+
+#[test]
+fn checkpoint() {
+    let path = std::env::temp_dir().join("neatcore_checkpoint.json");
+    let path_string = path.to_str().unwrap().to_string();
+
+    // Invalid networks get an infinite fitness, which has to survive the checkpoint too
+    let fitness = |network: &mut network::NeuralNetwork| {
+        let output = network.run(vec![0.5, 1.0]).unwrap()[0];
+        if output < 0.0 { f64::NEG_INFINITY } else { -(output - 0.7).abs() }
+    };
+
+    let config = NeatConfig {
+        seed: Some(5),
+        add_node_rate: 0.2,
+        add_connection_rate: 0.5,
+        checkpoint_interval: 4,
+        checkpoint_path: Some(path_string.clone()),
+        ..NeatConfig::default()
+    };
+
+    let mut core = Core::init(20, None, None, (2, 1), config).unwrap();
+    core.train(8, fitness).unwrap();
+
+    // The last checkpoint was written after generation 8
+    let mut resumed = Core::resume(&path).unwrap();
+    assert_eq!(resumed.generation(), 8);
+
+    for _ in 0..6 {
+        assert_eq!(core.evolve(fitness).unwrap(), resumed.evolve(fitness).unwrap());
+    }
+
+    assert_eq!(core.generation(), resumed.generation());
+    assert_eq!(core.species_count(), resumed.species_count());
+    assert_eq!(core.champion(), resumed.champion());
+
+    // A NaN weight would be written as null, so the last good checkpoint, from generation 12, is kept instead
+    let genome: Genome = vec![Gene::new(0, f64::NAN, true)].into_iter().collect();
+    let diverged = Core::init(10, Some(&genome), Some(vec![(1, 3)]), (2, 1), NeatConfig::default()).unwrap();
+
+    assert!(matches!(diverged.checkpoint(&path), Err(NeatError::NonFiniteWeight(0))));
+    assert_eq!(Core::resume(&path).unwrap().generation(), 12);
+
+    // Checkpoints that parse but dont match their population are refused instead of panicking later
+    let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+
+    let mut truncated = json.clone();
+    truncated["fit_arr"].as_array_mut().unwrap().pop();
+    std::fs::write(&path, truncated.to_string()).unwrap();
+    assert!(matches!(Core::resume(&path), Err(NeatError::Checkpoint(_))));
+
+    let mut edited = json;
+    edited["species"][0]["members"][0] = serde_json::json!(999);
+    std::fs::write(&path, edited.to_string()).unwrap();
+    assert!(matches!(Core::resume(&path), Err(NeatError::Checkpoint(_))));
+
+    std::fs::remove_file(&path).unwrap();
+
+    let config = NeatConfig { checkpoint_interval: 4, ..NeatConfig::default() };
+    assert!(matches!(Core::init(10, None, None, (1, 1), config), Err(NeatError::Config(_))));
+}
+
+#[test]
+fn add_node() {
+    // Only node additions, with an Identity hidden activation so splits change nothing
+    // Crossover and toggling could enable a split gene again, so they are turned off too
+    let config = NeatConfig {
+        seed: Some(9),
+        add_node_rate: 1.0,
+        add_connection_rate: 0.0,
+        weight_change_rate: 0.0,
+        toggle_rate: 0.0,
+        crossover_rate: 0.0,
+        hidden_activation: network::Activation::Identity,
+        ..NeatConfig::default()
+    };
+
+    let genome: Genome = vec![Gene::new(0, 0.7, true), Gene::new(1, -0.2, true)].into_iter().collect();
+    let mut core = Core::init(10, Some(&genome), Some(vec![(1, 2), (0, 2)]), (1, 1), config.clone()).unwrap();
+
+    for _ in 0..3 {
+        core.evolve(|network| network.run(vec![1.0]).unwrap()[0]).unwrap();
+    }
+
+    let mut split = false;
+
+    for i in 0..10 {
+        for x in [-1.0, 0.5, 2.0] {
+            let output = core.run(i, vec![x]).unwrap()[0];
+            assert!((output - (0.7 * x - 0.2)).abs() < 1e-9);
+        }
+
+        // Every split disables one gene and adds two enabled ones
+        let genome = core.saved_network(i).genome;
+        let disabled = genome.iter().filter(|gene| !gene.enabled).count();
+
+        assert_eq!(genome.len(), 2 + 2 * disabled);
+        split |= disabled > 0;
+    }
+
+    assert!(split);
+
+    // Neuron 5 is the first split neuron the table hands out, but these genomes already use it for 2 -> 5 -> 4
+    // so splitting 1 -> 3 has to take a fresh neuron, the same one in every genome
+    let genome: Genome = vec![Gene::new(0, 0.7, true), Gene::new(1, 0.5, true), Gene::new(2, 0.5, true)].into_iter().collect();
+    let mut core = Core::init(10, Some(&genome), Some(vec![(1, 3), (2, 5), (5, 4)]), (2, 2), config).unwrap();
+
+    let inputs = [vec![1.0, 0.0], vec![0.0, 1.0], vec![-2.0, 3.0]];
+    let expected: Vec<Vec<f64>> = inputs.iter().map(|inputs| core.run(0, inputs.clone()).unwrap()).collect();
+
+    core.evolve(|network| network.run(vec![1.0, 1.0]).unwrap()[0]).unwrap();
+
+    let mut split_neurons = HashSet::new();
+
+    for i in 0..10 {
+        for (inputs, expected) in inputs.iter().zip(expected.iter()) {
+            assert_eq!(&core.run(i, inputs.clone()).unwrap(), expected);
+        }
+
+        let saved = core.saved_network(i);
+
+        for gene in saved.genome.iter() {
+            let innovation = &saved.table.innovations[gene.innovation];
+
+            if innovation.from == 1 && innovation.to > 5 {
+                split_neurons.insert(innovation.to);
+            }
+        }
+    }
+
+    assert_eq!(split_neurons.len(), 1);
+    assert!(!split_neurons.contains(&5));
+}
+
+#[test]
+fn add_connection() {
+    // Only connection additions, with a fixed weight so the new genes can be told apart
+    let config = NeatConfig {
+        seed: Some(4),
+        add_node_rate: 0.0,
+        add_connection_rate: 1.0,
+        weight_change_rate: 0.0,
+        toggle_rate: 0.0,
+        crossover_rate: 0.0,
+        new_connection_weight: WeightDistribution::Constant(0.3),
+        ..NeatConfig::default()
+    };
+
+    let mut core = Core::init(10, None, None, (2, 1), config).unwrap();
+
+    for _ in 0..5 {
+        core.evolve(|network| network.run(vec![1.0, 1.0]).unwrap()[0]).unwrap();
+    }
+
+    // Bias and both inputs to the output is all a feedforward network without hidden neurons can have
+    for i in 0..10 {
+        let genome = core.saved_network(i).genome;
+
+        assert_eq!(genome.len(), 3);
+        assert!(genome.iter().all(|gene| gene.enabled && gene.weight == 0.3));
+    }
+
+    assert!(core.full_genomes() > 0);
+
+    let config = NeatConfig { new_connection_weight: WeightDistribution::Uniform { min: 1.0, max: -1.0 }, ..NeatConfig::default() };
+    assert!(matches!(Core::init(10, None, None, (1, 1), config), Err(NeatError::Config(_))));
+}
+
+#[test]
+fn mutate_weights() {
+    let genome: Genome = vec![Gene::new(0, 0.0, true), Gene::new(1, 0.0, false)].into_iter().collect();
+
+    // Big Gaussian steps on every enabled gene, clamped to [-1, 1]
+    let config = NeatConfig {
+        seed: Some(6),
+        add_node_rate: 0.0,
+        add_connection_rate: 0.0,
+        toggle_rate: 0.0,
+        crossover_rate: 0.0,
+        weight_change_rate: 1.0,
+        weight_perturb_rate: 1.0,
+        weight_replace_rate: 0.0,
+        weight_perturb_sigma: 5.0,
+        weight_range: Some((-1.0, 1.0)),
+        ..NeatConfig::default()
+    };
+
+    let mut core = Core::init(10, Some(&genome), Some(vec![(1, 2), (0, 2)]), (1, 1), config.clone()).unwrap();
+    core.train(3, |network| network.run(vec![1.0]).unwrap()[0]).unwrap();
+
+    for i in 0..10 {
+        let genome = core.saved_network(i).genome;
+
+        assert!((-1.0..=1.0).contains(&genome.find_weight(0).unwrap()));
+        assert_eq!(genome.find_weight(1), Some(0.0)); // Disabled genes are left alone
+    }
+
+    // Every enabled gene replaced, without elites only the carried over champion keeps the old weight
+    let config = NeatConfig {
+        weight_perturb_rate: 0.0,
+        weight_replace_rate: 1.0,
+        new_connection_weight: WeightDistribution::Constant(0.42),
+        elitism: 0,
+        ..config
+    };
+
+    let mut core = Core::init(10, Some(&genome), Some(vec![(1, 2), (0, 2)]), (1, 1), config).unwrap();
+    core.evolve(|network| network.run(vec![1.0]).unwrap()[0]).unwrap();
+
+    let weights: Vec<Option<f64>> = (0..10).map(|i| core.saved_network(i).genome.find_weight(0)).collect();
+
+    assert_eq!(weights.iter().filter(|weight| **weight == Some(0.42)).count(), 9);
+    assert!(weights.contains(&Some(0.0)));
+
+    let config = NeatConfig { weight_perturb_rate: 0.8, weight_replace_rate: 0.3, ..NeatConfig::default() };
+    assert!(matches!(Core::init(10, None, None, (1, 1), config), Err(NeatError::Config(_))));
+}
+
+#[test]
+fn deletion() {
+    // 1 -> 10 -> 2 through a hidden neuron, and the bias straight to the output
+    let genome: Genome = vec![Gene::new(0, 0.5, true), Gene::new(1, 0.5, true), Gene::new(2, 0.5, true)].into_iter().collect();
+    let innovations = Some(vec![(1, 10), (10, 2), (0, 2)]);
+
+    let only = |delete_connection_rate: f64, delete_neuron_rate: f64| NeatConfig {
+        seed: Some(8),
+        add_node_rate: 0.0,
+        add_connection_rate: 0.0,
+        weight_change_rate: 0.0,
+        toggle_rate: 0.0,
+        crossover_rate: 0.0,
+        elitism: 0,
+        delete_connection_rate,
+        delete_neuron_rate,
+        ..NeatConfig::default()
+    };
+
+    // Removing the hidden neuron takes both of its connectors, only the carried over champion is left whole
+    let mut core = Core::init(10, Some(&genome), innovations.clone(), (1, 1), only(0.0, 1.0)).unwrap();
+    core.evolve(|network| network.run(vec![1.0]).unwrap()[0]).unwrap();
+
+    let ids: Vec<Vec<usize>> = (0..10).map(|i| core.saved_network(i).genome.iter().map(|gene| gene.innovation).collect()).collect();
+
+    assert_eq!(ids.iter().filter(|ids| **ids == vec![2]).count(), 9);
+    assert!(ids.contains(&vec![0, 1, 2]));
+
+    // Losing either side of the hidden neuron drops the other side too
+    let mut core = Core::init(10, Some(&genome), innovations, (1, 1), only(1.0, 0.0)).unwrap();
+    core.evolve(|network| network.run(vec![1.0]).unwrap()[0]).unwrap();
+
+    let ids: Vec<Vec<usize>> = (0..10).map(|i| core.saved_network(i).genome.iter().map(|gene| gene.innovation).collect()).collect();
+
+    assert_eq!(ids.iter().filter(|ids| **ids == vec![2] || **ids == vec![0, 1]).count(), 9);
+    assert!(ids.contains(&vec![0, 1, 2]));
+
+    // Growing and shrinking together always leaves buildable networks with no dead end hidden neurons
+    let config = NeatConfig {
+        seed: Some(8),
+        add_node_rate: 0.5,
+        add_connection_rate: 0.5,
+        delete_connection_rate: 0.3,
+        delete_neuron_rate: 0.3,
+        ..NeatConfig::default()
+    };
+
+    let mut core = Core::init(20, None, None, (2, 1), config).unwrap();
+    core.train(30, |network| network.run(vec![1.0, -1.0]).unwrap()[0]).unwrap();
+
+    for i in 0..20 {
+        let saved = core.saved_network(i);
+        saved.network().unwrap();
+
+        let connections: Vec<(usize, usize)> = saved.genome.iter()
+            .map(|gene| (saved.table.innovations[gene.innovation].from, saved.table.innovations[gene.innovation].to))
+            .collect();
+
+        for (from, to) in connections.iter() {
+            for neuron in [*from, *to] {
+                if neuron > 3 {
+                    assert!(connections.iter().any(|(f, t)| *t == neuron && *f != neuron));
+                    assert!(connections.iter().any(|(f, t)| *f == neuron && *t != neuron));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn nan_fitness() {
+    let config = NeatConfig { seed: Some(12), ..NeatConfig::default() };
+    let mut core = Core::init(10, None, None, (1, 1), config.clone()).unwrap();
+
+    // One NaN in the first generation, then everything scores 1.0
+    let mut calls = 0;
+    core.train(4, |_| {
+        calls += 1;
+        if calls == 1 { f64::NAN } else { 1.0 }
+    }).unwrap();
+
+    assert_eq!(core.champion().unwrap().1, 1.0);
+
+    // Same through evolve_parallel, where every genome of the first generation is NaN
+    let mut core = Core::init(10, None, None, (1, 1), NeatConfig { threads: 2, ..config }).unwrap();
+    assert_eq!(core.evolve_parallel(|_| f64::NAN).unwrap(), f64::NEG_INFINITY);
+
+    core.evolve_parallel(|_| 1.0).unwrap();
+    assert_eq!(core.champion().unwrap().1, 1.0);
+
+    // Evaluation turns NaN into the worst finite fitness, here 1.0, and the allocation treats a NaN species as the worst too
+    // so the quotas stay proportional either way
+    let sizes = [2, 2];
+    let species = [vec![0, 1], vec![2, 3]];
+
+    for fit_arr in [[2.0, 2.0, 1.0, 1.0], [2.0, 2.0, 1.0, f64::NAN]] {
+        let shared: Vec<f64> = species.iter().map(|members| neatcore::shared_fitness(members, &fit_arr)).collect();
+        assert_eq!(neatcore::allocate_offspring(&shared, &sizes, 1.0, 8), vec![8, 0]);
+    }
+}
+
+#[test]
+fn allocate_offspring() {
+    let fit_arr = [4.0, 4.0, 2.0, 0.0, 0.0];
+    let species = [vec![0, 1], vec![2], vec![3, 4]];
+
+    let shared: Vec<f64> = species.iter().map(|members| neatcore::shared_fitness(members, &fit_arr)).collect();
+    let sizes: Vec<usize> = species.iter().map(|members| members.len()).collect();
+    assert_eq!(shared, vec![4.0, 2.0, 0.0]);
+
+    // Shares are 3.33, 1.67 and 0, the one spare child goes to the largest remainder
+    let quotas = neatcore::allocate_offspring(&shared, &sizes, 0.0, 5);
+    assert_eq!(quotas, vec![3, 2, 0]);
+    assert_eq!(quotas.iter().sum::<usize>(), 5);
+
+    // Negative fitness is shifted by the worst genome, which leaves the worst species with nothing
+    let fit_arr = [-1.0, -1.0, -3.0];
+    let species = [vec![0, 1], vec![2]];
+
+    let shared: Vec<f64> = species.iter().map(|members| neatcore::shared_fitness(members, &fit_arr)).collect();
+    assert_eq!(neatcore::allocate_offspring(&shared, &[2, 1], -3.0, 7), vec![7, 0]);
+
+    // Every species equally bad, so the quotas follow the species sizes
+    let fit_arr = [1.0; 4];
+    let species = [vec![0, 1, 2], vec![3]];
+
+    let shared: Vec<f64> = species.iter().map(|members| neatcore::shared_fitness(members, &fit_arr)).collect();
+    assert_eq!(neatcore::allocate_offspring(&shared, &[3, 1], 1.0, 4), vec![3, 1]);
+
+    let quotas = neatcore::allocate_offspring(&shared, &[3, 1], 1.0, 6);
+    assert_eq!(quotas.iter().sum::<usize>(), 6);
+    assert!(quotas[0] >= 3 && quotas[1] >= 1);
+
+    // An invalid network scoring -inf makes its species the worst, the rest are still shifted by the worst finite fitness
+    let fit_arr = [5.0, 5.0, 1.0, 1.0, f64::NEG_INFINITY];
+    let species = [vec![0, 1], vec![2, 3], vec![4]];
+
+    let shared: Vec<f64> = species.iter().map(|members| neatcore::shared_fitness(members, &fit_arr)).collect();
+    assert_eq!(neatcore::allocate_offspring(&shared, &[2, 2, 1], 1.0, 10), vec![10, 0, 0]);
+
+    let fit_arr = [5.0, 3.0, 1.0, 1.0, f64::NEG_INFINITY];
+    let shared: Vec<f64> = [vec![0], vec![1], vec![2, 3, 4]].iter().map(|members| neatcore::shared_fitness(members, &fit_arr)).collect();
+    assert_eq!(neatcore::allocate_offspring(&shared, &[1, 1, 3], 1.0, 10), vec![7, 3, 0]);
+}
+
+#[test]
+fn cull_stagnant() {
+    // Fitness never changes, so every species stagnates and the champion is the genome from the first generation
+    // Mutated children all get weight 1.0, so after one generation there are two species, the single elite
+    // with the champion's weight 0.0 and everyone else
+    // 9 genomes so the shared fitness of the big species is exactly 1.0, like the small one
+    let config = NeatConfig {
+        seed: Some(4),
+        c3: 1.0,
+        compat_threshold: 0.5,
+        stagnation_limit: 2,
+        add_node_rate: 0.0,
+        add_connection_rate: 0.0,
+        toggle_rate: 0.0,
+        weight_change_rate: 1.0,
+        weight_perturb_rate: 0.0,
+        weight_replace_rate: 1.0,
+        new_connection_weight: WeightDistribution::Constant(1.0),
+        elitism: 1,
+        elitism_min_size: 1,
+        crossover_rate: 0.0,
+        ..NeatConfig::default()
+    };
+
+    let genome: Genome = vec![Gene::new(0, 0.0, true)].into_iter().collect();
+    let mut core = Core::init(9, Some(&genome), Some(vec![(1, 2)]), (1, 1), config).unwrap();
+
+    let mut counts = Vec::new();
+
+    for _ in 0..5 {
+        core.evolve(|_| 1.0).unwrap();
+        counts.push(core.species_count());
+    }
+
+    // The champion's species goes stagnant first but is kept, the other one is removed a generation later
+    assert_eq!(counts, vec![1, 2, 2, 2, 1]);
+
+    let (champion, _) = core.champion().unwrap();
+    assert_eq!(champion, &genome);
+    assert!((0..9).any(|i| core.saved_network(i).genome == genome));
+}
+
+#[test]
+fn distance() {
+    let config = NeatConfig { c1: 1.0, c2: 2.0, c3: 4.0, ..NeatConfig::default() };
+
+    // Matching: 0, disjoint: 1, 3 and 2, excess: 4 and 5
+    let genome_1: Genome = vec![Gene::new(0, 1.0, true), Gene::new(1, 2.0, true), Gene::new(3, 0.5, true)].into_iter().collect();
+    let genome_2: Genome = vec![Gene::new(0, 1.5, false), Gene::new(2, 0.0, true), Gene::new(4, 0.0, true), Gene::new(5, 0.0, true)].into_iter().collect();
+
+    // 1 * 2 + 2 * 3 + 4 * 0.5, not normalised because both genomes are under 20 genes
+    assert_eq!(neatcore::distance(&genome_1, &genome_2, &config), 10.0);
+    assert_eq!(neatcore::distance(&genome_2, &genome_1, &config), 10.0);
+    assert_eq!(neatcore::distance(&genome_1, &genome_1, &config), 0.0);
+
+    // Average weight difference over every matching gene
+    let genome_3: Genome = vec![Gene::new(0, 0.0, true), Gene::new(1, 1.0, true), Gene::new(3, 0.0, true)].into_iter().collect();
+    assert_eq!(neatcore::distance(&genome_1, &genome_3, &config), 4.0 * (1.0 + 1.0 + 0.5) / 3.0);
+
+    // 10 excess genes, divided by the larger genome's length from 20 genes on
+    let genes = |count: usize| -> Genome { (0..count).map(|i| Gene::new(i, 0.0, true)).collect() };
+
+    assert_eq!(neatcore::distance(&genes(19), &genes(9), &config), 10.0);
+    assert_eq!(neatcore::distance(&genes(20), &genes(10), &config), 0.5);
+}
+
+#[test]
+fn identical_genomes_share_species() {
+    let config = NeatConfig {
+        seed: Some(2),
+        add_node_rate: 0.0,
+        add_connection_rate: 0.0,
+        toggle_rate: 0.0,
+        weight_change_rate: 0.0,
+        ..NeatConfig::default()
+    };
+
+    let genome: Genome = vec![Gene::new(0, 0.5, true)].into_iter().collect();
+    let mut core = Core::init(10, Some(&genome), Some(vec![(1, 2)]), (1, 1), config).unwrap();
+
+    // Every genome is a copy of the first one, so they all join the species it started
+    for _ in 0..3 {
+        core.evolve(|network| network.run(vec![1.0]).unwrap()[0]).unwrap();
+        assert_eq!(core.species_count(), 1);
+    }
+}
+
+#[test]
+fn champion_survives() {
+    // Default elitism only keeps elites in species of 5 or more, the champion is carried over regardless
+    for seed in 0..5 {
+        let config = NeatConfig { seed: Some(seed), ..NeatConfig::default() };
+        let mut core = Core::init(30, None, None, (2, 1), config).unwrap();
+
+        for _ in 0..30 {
+            core.evolve(|network| -(network.run(vec![1.0, 2.0]).unwrap()[0] - 3.0).abs()).unwrap();
+
+            let champion = core.champion().unwrap().0.clone();
+            assert!((0..30).any(|i| core.saved_network(i).genome == champion));
+        }
+    }
+}
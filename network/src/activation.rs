@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Activation {
+    Identity,
+    Sigmoid,
+    SteepenedSigmoid, // 1 / (1 + e^(-4.9x)), from the NEAT paper
+    Tanh,
+    Relu,
+    Gaussian,
+    Sin,
+    Step,
+}
+
+// Defaults for neurons that arent inputs or the bias, which always use Identity
+pub const HIDDEN_ACTIVATION: Activation = Activation::SteepenedSigmoid;
+pub const OUTPUT_ACTIVATION: Activation = Activation::Identity;
+
+impl Activation {
+    pub fn apply(&self, x: f64) -> f64 {
+        match self {
+            Activation::Identity => x,
+            Activation::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+            Activation::SteepenedSigmoid => 1.0 / (1.0 + (-4.9 * x).exp()),
+            Activation::Tanh => x.tanh(),
+            Activation::Relu => x.max(0.0),
+            Activation::Gaussian => (-x * x).exp(),
+            Activation::Sin => x.sin(),
+            Activation::Step => if x > 0.0 { 1.0 } else { 0.0 },
+        }
+    }
+
+    // d/dx of apply, Step is treated as flat everywhere
+    pub fn derivative(&self, x: f64) -> f64 {
+        match self {
+            Activation::Identity => 1.0,
+            Activation::Sigmoid => {
+                let y = self.apply(x);
+                y * (1.0 - y)
+            },
+            Activation::SteepenedSigmoid => {
+                let y = self.apply(x);
+                4.9 * y * (1.0 - y)
+            },
+            Activation::Tanh => 1.0 - x.tanh().powi(2),
+            Activation::Relu => if x > 0.0 { 1.0 } else { 0.0 },
+            Activation::Gaussian => -2.0 * x * (-x * x).exp(),
+            Activation::Sin => x.cos(),
+            Activation::Step => 0.0,
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::{Connector, NeuralNetwork, NetworkError};
+
+impl NeuralNetwork {
+    // Runs the network forward, then walks the layers backwards
+    // Returns the gradient of every connector (same indices as connectors) and the loss, 0.5 * sum((output - target)^2)
+    // Recurrent connectors get a gradient from the previous values, but nothing is propagated through time
+    pub fn gradients(&mut self, inputs: Vec<f64>, targets: &[f64]) -> Result<(Vec<f64>, f64), NetworkError> {
+        if targets.len() != self.neuron_levels.1.len() {
+            return Err(NetworkError::OutputCount { expected: self.neuron_levels.1.len(), got: targets.len() });
+        }
+
+        let outputs = self.run(inputs)?;
+
+        // dLoss / dValue of every neuron, by index in neurons
+        let mut value_grads: Vec<f64> = vec![0.0; self.neurons.len()];
+        let mut gradients: Vec<f64> = vec![0.0; self.connectors.len()];
+        let mut loss = 0.0;
+
+        for (i, output_neuron) in self.neuron_levels.1.iter().enumerate() {
+            let error = outputs[i] - targets[i];
+
+            loss += 0.5 * error * error;
+            value_grads[*self.neuron_map.get(output_neuron).unwrap()] += error;
+        }
+
+        for layer in self.layers.iter().rev() {
+            for neuron_id in layer {
+                let index = *self.neuron_map.get(neuron_id).unwrap();
+                let neuron = &self.neurons[index];
+
+                let sum_grad = value_grads[index] * neuron.activation.derivative(neuron.sum);
+
+                for connector in neuron.from_arr.iter() {
+                    let Connector { from, weight, .. } = self.connectors[*connector];
+                    let from_index = *self.neuron_map.get(&from).unwrap();
+
+                    gradients[*connector] += sum_grad * self.neurons[from_index].value;
+                    value_grads[from_index] += sum_grad * weight;
+                }
+            }
+        }
+
+        for connector in self.recurrent.iter() {
+            let Connector { from, to, .. } = self.connectors[*connector];
+
+            let to_index = *self.neuron_map.get(&to).unwrap();
+            let to_neuron = &self.neurons[to_index];
+
+            let sum_grad = value_grads[to_index] * to_neuron.activation.derivative(to_neuron.sum);
+            gradients[*connector] += sum_grad * self.get_neuron(&from).prev_value;
+        }
+
+        Ok((gradients, loss))
+    }
+
+    // Gradient descent step, disabled connectors are left alone
+    pub fn apply_gradients(&mut self, gradients: &[f64], learning_rate: f64) {
+        for (connector, gradient) in self.connectors.iter_mut().zip(gradients.iter()) {
+            if connector.enabled {
+                connector.weight -= learning_rate * gradient;
+            }
+        }
+    }
+}
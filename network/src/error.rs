@@ -0,0 +1,32 @@
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum NetworkError {
+    UnknownGene(usize), // Gene in a genome that doesnt exist in the innovation table
+    GenomeLengthMismatch, // The three vectors of a genome arent the same length
+    InputConnected(usize), // Input neuron with connectors going into it
+    UnknownNeuron(usize),
+    InputCount { expected: usize, got: usize },
+    OutputCount { expected: usize, got: usize },
+    BatchShape, // Batch inputs and outputs dont have the same number of rows
+    LayeringFailed(Vec<Vec<usize>>),
+    Encoding(String), // Png encoder failed
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetworkError::UnknownGene(id) => write!(f, "gene {} is not in the innovation table", id),
+            NetworkError::GenomeLengthMismatch => write!(f, "genome vectors are not the same length"),
+            NetworkError::InputConnected(id) => write!(f, "input neuron {} has connectors going into it", id),
+            NetworkError::UnknownNeuron(id) => write!(f, "neuron {} is not in the network", id),
+            NetworkError::InputCount { expected, got } => write!(f, "expected {} inputs, got {}", expected, got),
+            NetworkError::OutputCount { expected, got } => write!(f, "expected {} outputs, got {}", expected, got),
+            NetworkError::BatchShape => write!(f, "batch inputs and outputs do not have the same number of rows"),
+            NetworkError::LayeringFailed(layers) => write!(f, "layering failed, layers: {:?}", layers),
+            NetworkError::Encoding(error) => write!(f, "png encoding failed: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
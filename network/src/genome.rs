@@ -0,0 +1,177 @@
+use crate::NetworkError;
+
+use serde::{Serialize, Deserialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Gene {
+    pub innovation: usize,
+    pub weight: f64,
+    pub enabled: bool,
+}
+
+// Genes are always kept sorted by innovation id, with at most one gene per innovation
+// Saved as a plain list of genes, which is sorted again on load
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(from = "Vec<Gene>", into = "Vec<Gene>")]
+pub struct Genome {
+    genes: Vec<Gene>,
+}
+
+impl Gene {
+    pub fn new(innovation: usize, weight: f64, enabled: bool) -> Self {
+        Self { innovation, weight, enabled }
+    }
+}
+
+impl Genome {
+    pub fn new() -> Self {
+        Self { genes: Vec::new() }
+    }
+
+    fn position(&self, innovation: usize) -> Result<usize, usize> {
+        self.genes.binary_search_by_key(&innovation, |gene| gene.innovation)
+    }
+
+    // Replaces the gene with the same innovation if there is one, and returns it
+    pub fn insert(&mut self, gene: Gene) -> Option<Gene> {
+        match self.position(gene.innovation) {
+            Ok(i) => Some(std::mem::replace(&mut self.genes[i], gene)),
+            Err(i) => {
+                self.genes.insert(i, gene);
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, innovation: usize) -> Option<Gene> {
+        self.position(innovation).ok().map(|i| self.genes.remove(i))
+    }
+
+    pub fn get(&self, innovation: usize) -> Option<&Gene> {
+        self.position(innovation).ok().map(|i| &self.genes[i])
+    }
+
+    // Changing the innovation of the gene breaks the ordering, use remove and insert for that
+    pub fn get_mut(&mut self, innovation: usize) -> Option<&mut Gene> {
+        self.position(innovation).ok().map(|i| &mut self.genes[i])
+    }
+
+    pub fn contains(&self, innovation: usize) -> bool {
+        self.position(innovation).is_ok()
+    }
+
+    pub fn find_weight(&self, innovation: usize) -> Option<f64> {
+        self.get(innovation).map(|gene| gene.weight)
+    }
+
+    // Returns false if the genome doesnt have the gene
+    pub fn set_enabled(&mut self, innovation: usize, enabled: bool) -> bool {
+        match self.get_mut(innovation) {
+            Some(gene) => {
+                gene.enabled = enabled;
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn enable(&mut self, innovation: usize) -> bool {
+        self.set_enabled(innovation, true)
+    }
+
+    pub fn disable(&mut self, innovation: usize) -> bool {
+        self.set_enabled(innovation, false)
+    }
+
+    // In innovation order
+    pub fn iter(&self) -> std::slice::Iter<'_, Gene> {
+        self.genes.iter()
+    }
+
+    // Same warning as get_mut, only weights and enabled flags should be changed
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Gene> {
+        self.genes.iter_mut()
+    }
+
+    pub fn genes(&self) -> &[Gene] {
+        &self.genes
+    }
+
+    pub fn len(&self) -> usize {
+        self.genes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.genes.is_empty()
+    }
+
+    pub fn max_innovation(&self) -> Option<usize> {
+        self.genes.last().map(|gene| gene.innovation)
+    }
+}
+
+impl FromIterator<Gene> for Genome {
+    fn from_iter<I>(iter: I) -> Self where I: IntoIterator<Item = Gene> {
+        let mut genome = Genome::new();
+
+        for gene in iter {
+            genome.insert(gene);
+        }
+
+        genome
+    }
+}
+
+impl From<Vec<Gene>> for Genome {
+    fn from(genes: Vec<Gene>) -> Self {
+        genes.into_iter().collect()
+    }
+}
+
+impl From<Genome> for Vec<Gene> {
+    fn from(genome: Genome) -> Self {
+        genome.genes
+    }
+}
+
+impl<'a> IntoIterator for &'a Genome {
+    type Item = &'a Gene;
+    type IntoIter = std::slice::Iter<'a, Gene>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.genes.iter()
+    }
+}
+
+// Old tuple form, (innovation ids, weights, enabled flags)
+impl TryFrom<(Vec<usize>, Vec<f64>, Vec<bool>)> for Genome {
+    type Error = NetworkError;
+
+    fn try_from(tuple: (Vec<usize>, Vec<f64>, Vec<bool>)) -> Result<Self, Self::Error> {
+        let (innovations, weights, enabled) = tuple;
+
+        if innovations.len() != weights.len() || innovations.len() != enabled.len() {
+            return Err(NetworkError::GenomeLengthMismatch);
+        }
+
+        Ok(innovations.into_iter()
+            .zip(weights)
+            .zip(enabled)
+            .map(|((innovation, weight), enabled)| Gene::new(innovation, weight, enabled))
+            .collect())
+    }
+}
+
+impl From<Genome> for (Vec<usize>, Vec<f64>, Vec<bool>) {
+    fn from(genome: Genome) -> Self {
+        let mut tuple = (Vec::new(), Vec::new(), Vec::new());
+
+        for gene in genome.genes {
+            tuple.0.push(gene.innovation);
+            tuple.1.push(gene.weight);
+            tuple.2.push(gene.enabled);
+        }
+
+        tuple
+    }
+}
@@ -1,7 +1,7 @@
-use crate::NeuralNetwork;
-use std::collections::HashSet;
+use crate::{NeuralNetwork, NetworkError};
+use std::collections::{HashMap, HashSet};
 
-pub fn layer_network(network: &mut NeuralNetwork) -> Vec<Vec<usize>> {
+pub fn layer_network(network: &mut NeuralNetwork) -> Result<Vec<Vec<usize>>, NetworkError> {
     let mut layers: Vec<Vec<usize>> = Vec::new();
     let mut queue: Vec<usize> = Vec::new();
 
@@ -56,28 +56,74 @@ pub fn layer_network(network: &mut NeuralNetwork) -> Vec<Vec<usize>> {
     .cloned()
     .collect::<Vec<usize>>();
 
-    if layers[1] == [] {
+    if layers[1].is_empty() {
         layers.remove(1);
     }
 
+    // The hash sets above dont keep an order, sorting makes the layers (and firing order) the same every run
+    for layer in layers.iter_mut() {
+        layer.sort();
+    }
+
+    // Every neuron has to end up in exactly one layer
+    let len: usize = layers.iter().map(|layer| layer.len()).sum();
+
+    if len != network.neurons.len() {
+        return Err(NetworkError::LayeringFailed(layers));
+    }
+
+    Ok(layers)
+}
+
+// Finds the connectors that close a cycle (back edges of a depth first search, self loops included)
+// Starts from the bias and inputs, then every other neuron in order, so the same network always gives the same connectors
+// A feedforward network never has any
+pub fn find_recurrent(network: &NeuralNetwork) -> HashSet<usize> {
+    let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
+
+    for (i, connector) in network.connectors.iter().enumerate() {
+        outgoing.entry(connector.from).or_default().push(i);
+    }
+
+    let mut starts: Vec<usize> = vec![0];
+    starts.extend(&network.neuron_levels.0);
+    starts.extend(network.neurons.iter().map(|neuron| neuron.id));
 
-    #[cfg(debug_assertions)]
-    {
-        let mut len: usize = 0;
-        for layer in layers.iter() {
-            len += layer.len();
+    let mut recurrent: HashSet<usize> = HashSet::new();
+    let mut visited: HashSet<usize> = HashSet::new();
+    let mut on_stack: HashSet<usize> = HashSet::new();
+
+    for start in starts {
+        if !visited.insert(start) {
+            continue;
         }
 
-        if len != network.neurons.len() {
-            println!("\nError");
+        on_stack.insert(start);
+
+        // (neuron, index of the next outgoing connector to look at)
+        let mut stack: Vec<(usize, usize)> = vec![(start, 0)];
 
-            for connection in network.connectors.iter() {
-                println!("From: {:?}, To: {:?}", connection.from, connection.to);
+        while let Some((neuron, next)) = stack.pop() {
+            let edges = outgoing.get(&neuron).map(|edges| edges.as_slice()).unwrap_or(&[]);
+
+            if next == edges.len() {
+                on_stack.remove(&neuron);
+                continue;
+            }
+
+            stack.push((neuron, next + 1));
+
+            let connector = edges[next];
+            let to = network.connectors[connector].to;
+
+            if on_stack.contains(&to) {
+                recurrent.insert(connector);
+            } else if visited.insert(to) {
+                on_stack.insert(to);
+                stack.push((to, 0));
             }
-            
-            panic!("Layering failed at layering. Layers: {:?}", layers);
         }
     }
 
-    layers
-}
\ No newline at end of file
+    recurrent
+}
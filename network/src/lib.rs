@@ -1,11 +1,21 @@
 mod layering;
-
-use layering::layer_network;
+mod activation;
+mod backprop;
+mod plan;
+mod error;
+mod genome;
+mod render;
+mod raster;
+
+pub use activation::{Activation, HIDDEN_ACTIVATION, OUTPUT_ACTIVATION};
+pub use plan::CompiledNetwork;
+pub use error::NetworkError;
+pub use genome::{Gene, Genome};
+pub use render::Role;
+use layering::{layer_network, find_recurrent};
 use innovation::{InnovationTable, Type};
 
 use std::collections::{HashMap, HashSet};
-use std::thread;
-use std::time;
 
 pub type Layers = Vec<Vec<usize>>;
 
@@ -14,6 +24,7 @@ pub struct Connector {
     pub to: usize,
     pub weight: f64,
     pub id: usize,
+    pub enabled: bool, // Disabled connectors keep a weight of 0
 }
 
 pub struct Neuron {
@@ -21,6 +32,9 @@ pub struct Neuron {
     pub from_arr: Vec<usize>,
     pub to_arr: Vec<usize>,
     pub value: f64,
+    pub prev_value: f64, // Value from the last run, read by recurrent connectors
+    pub sum: f64, // Value before the activation in the last run, for backprop
+    pub activation: Activation, // Applied once every connector going into the neuron has fired
     /*
     Calls is just for layers
     its incremented when the neuron is mentioned in the from connection of a connector
@@ -29,24 +43,6 @@ pub struct Neuron {
     pub calls: usize,
 }
 
-#[derive(Clone)]
-pub struct Genome(pub Vec<usize>, pub Vec<f64>, pub Vec<bool>);
-
-impl Genome {
-    pub fn new() -> Self {
-        Self(Vec::new(), Vec::new(), Vec::new())
-    }
-
-    pub fn find_weight(&self, id: usize) -> Option<f64> {
-        let index = self.0.iter().position(|&x| x == id);
-
-        match index {
-            Some(i) => return Some(self.1[i]),
-            None => return None,
-        }
-    }
-}
-
 pub struct NeuralNetwork {
     pub neurons: Vec<Neuron>,
     neuron_map: HashMap<usize, usize>,
@@ -54,6 +50,7 @@ pub struct NeuralNetwork {
     pub connector_map: HashMap<(usize, usize), usize>,
     pub layers: Layers,
     pub neuron_levels: (Vec<usize>, Vec<usize>),
+    pub recurrent: Vec<usize>, // Connectors that close a cycle, they fire with the values of the previous run
     order: Vec<Vec<usize>>,
 }
 
@@ -67,30 +64,37 @@ impl NeuralNetwork {
             connector_map: HashMap::new(),
             layers: Vec::new(),
             neuron_levels: (Vec::new(), Vec::new()),
+            recurrent: Vec::new(),
             order: Vec::new(),
         }
     }
 
-    pub fn init(genome: &Genome, innovation_table: &InnovationTable) -> NeuralNetwork {
+    pub fn init(genome: &Genome, innovation_table: &InnovationTable) -> Result<NeuralNetwork, NetworkError> {
         let mut network = NeuralNetwork::new();
         let mut neurons: Vec<usize> = Vec::new();
 
         network.neuron_levels = (innovation_table.neuron_levels.0.clone(), innovation_table.neuron_levels.1.clone());
         
-        for i in 0..genome.0.len() {
+        for gene in genome.iter() {
 
-            let innovation = &innovation_table.innovations[genome.0[i]];
-            // An error here means that theres a gene in a genome that doesnt exist in the innov table
+            // Theres a gene in a genome that doesnt exist in the innov table
             // Probably something custom like using gene 14 or something
+            let innovation = innovation_table.innovations.get(gene.innovation)
+                .ok_or(NetworkError::UnknownGene(gene.innovation))?;
 
             if innovation.kind == Type::Neuron {
                 continue;
             }
 
+            // The bias counts as an input
+            if innovation.to == 0 || innovation_table.neuron_levels.0.contains(&innovation.to) {
+                return Err(NetworkError::InputConnected(innovation.to));
+            }
+
             let mut weight = 0.0;
 
-            if genome.2[i] {
-                weight = genome.1[i];
+            if gene.enabled {
+                weight = gene.weight;
             }
 
             let connector = Connector {
@@ -98,6 +102,7 @@ impl NeuralNetwork {
                 to: innovation.to,
                 weight,
                 id: innovation.id,
+                enabled: gene.enabled,
             };
             
             neurons.push(connector.from);
@@ -115,11 +120,22 @@ impl NeuralNetwork {
         neurons.retain(|x| seen_neurons.insert(*x));
 
         for neuron_id in neurons {
+            let activation = if neuron_id == 0 || innovation_table.neuron_levels.0.contains(&neuron_id) {
+                Activation::Identity
+            } else if innovation_table.neuron_levels.1.contains(&neuron_id) {
+                OUTPUT_ACTIVATION
+            } else {
+                HIDDEN_ACTIVATION
+            };
+
             let neuron = Neuron {
                 id: neuron_id,
                 from_arr: Vec::new(),
                 to_arr: Vec::new(),
                 value: 0.0,
+                prev_value: 0.0,
+                sum: 0.0,
+                activation,
 
                 // Just for layers
                 calls: 0,
@@ -150,26 +166,26 @@ impl NeuralNetwork {
             || <- to
             []
             || <- from
+
+            Recurrent connectors are left out, so the rest of the network can still be layered
          */
+        let recurrent = find_recurrent(&network);
+
         for i in 0..network.connectors.len() {
+            if recurrent.contains(&i) {
+                network.recurrent.push(i);
+                continue;
+            }
+
             network.neurons[*network.neuron_map.get(&network.connectors[i].from).unwrap()].to_arr.push(i);
             network.neurons[*network.neuron_map.get(&network.connectors[i].to).unwrap()].from_arr.push(i);
         }
-        
-        #[cfg(debug_assertions)]
-        {
-            for neuron in &network.neurons {
-                if innovation_table.neuron_levels.0.contains(&neuron.id) {
-                    assert_eq!(neuron.from_arr.len(), 0, "Input neurons should not have any connectors going from them");
-                }
-            }
-        }
 
-        network.layers = layer_network(&mut network);
-        
-        network.layers = network.layers;
+
+        network.layers = layer_network(&mut network)?;
         network.order = network.get_order();
-        network
+
+        Ok(network)
     }
 
     fn fire_connector(&mut self, connector: usize) {
@@ -182,15 +198,14 @@ impl NeuralNetwork {
         neuron_2.value += neuron_1_value * weight;
     }
 
-    fn prepare_inputs(&mut self, inputs: Vec<f64>) {
-        #[cfg(debug_assertions)]
-        {
-            if inputs.len() != self.neuron_levels.0.len() {
-                panic!("Inputs are not equal to the number of input neurons");
-            }
+    fn prepare_inputs(&mut self, inputs: &[f64]) -> Result<(), NetworkError> {
+        if inputs.len() != self.neuron_levels.0.len() {
+            return Err(NetworkError::InputCount { expected: self.neuron_levels.0.len(), got: inputs.len() });
         }
 
         for neuron in &mut self.neurons {
+            neuron.prev_value = neuron.value;
+
             if neuron.id == 0 {
                 neuron.value = 1.0;
             } else {
@@ -201,6 +216,8 @@ impl NeuralNetwork {
         for (i, input_neuron) in self.neuron_levels.0.iter().enumerate() {
             self.neurons[*self.neuron_map.get(input_neuron).unwrap()].value = inputs[i];
         }
+
+        Ok(())
     }
 
     fn get_order(&self) -> Layers {
@@ -210,7 +227,7 @@ impl NeuralNetwork {
             let mut layer_order: Vec<usize> = Vec::new();
 
             for neuron in layer {
-                layer_order.extend(self.neurons[*neuron].to_arr.iter());
+                layer_order.extend(self.get_neuron(neuron).to_arr.iter());
             }
 
             order.push(layer_order);
@@ -219,27 +236,102 @@ impl NeuralNetwork {
         order
     }
 
-    pub fn run(&mut self, inputs: Vec<f64>) -> Vec<f64> {
-        self.prepare_inputs(inputs);
+    pub fn run(&mut self, inputs: Vec<f64>) -> Result<Vec<f64>, NetworkError> {
+        let mut output: Vec<f64> = vec![0.0; self.neuron_levels.1.len()];
+        self.run_into(&inputs, &mut output)?;
+
+        Ok(output)
+    }
+
+    // Same as run, but writes into the callers buffer and doesnt allocate
+    pub fn run_into(&mut self, inputs: &[f64], outputs: &mut [f64]) -> Result<(), NetworkError> {
+        if outputs.len() != self.neuron_levels.1.len() {
+            return Err(NetworkError::OutputCount { expected: self.neuron_levels.1.len(), got: outputs.len() });
+        }
+
+        self.prepare_inputs(inputs)?;
         
-        let order = self.order.clone();
+        // Recurrent connectors carry the previous timestep
+        for i in 0..self.recurrent.len() {
+            let Connector { from, to, weight, .. } = self.connectors[self.recurrent[i]];
+
+            let neuron_1_value: f64 = self.get_neuron(&from).prev_value;
+            self.neurons[*self.neuron_map.get(&to).unwrap()].value += neuron_1_value * weight;
+        }
+
+        // Every neuron in a layer has had all of its connectors fired by the time the layer is reached
+        // Indexing instead of iterating so order doesnt have to be cloned
+        for i in 0..self.order.len() {
+            for j in 0..self.layers[i].len() {
+                let neuron = &mut self.neurons[*self.neuron_map.get(&self.layers[i][j]).unwrap()];
+                neuron.sum = neuron.value;
+                neuron.value = neuron.activation.apply(neuron.value);
+            }
 
-        for set in order {
-            for connector in set {
-                self.fire_connector(connector);
+            for j in 0..self.order[i].len() {
+                self.fire_connector(self.order[i][j]);
             }
         }
 
+        for (i, output_neuron) in self.neuron_levels.1.iter().enumerate() {
+            outputs[i] = self.get_neuron(output_neuron).value;
+        }
+
+        Ok(())
+    }
+
+    // Evaluates many rows at once, inputs and outputs are flat with one row after the other
+    // (stride is the number of input neurons for inputs and output neurons for outputs)
+    // Rows run in order, so a recurrent network treats them as consecutive timesteps
+    pub fn run_batch(&mut self, inputs: &[f64], outputs: &mut [f64]) -> Result<(), NetworkError> {
+        let input_stride = self.neuron_levels.0.len();
+        let output_stride = self.neuron_levels.1.len();
+
+        if output_stride == 0 || !outputs.len().is_multiple_of(output_stride) {
+            return Err(NetworkError::BatchShape);
+        }
+
+        let rows = outputs.len() / output_stride;
 
-        let mut output: Vec<f64> = Vec::new();
+        if inputs.len() != rows * input_stride {
+            return Err(NetworkError::BatchShape);
+        }
 
-        for output_neuron in &self.neuron_levels.1 {
-            output.push(
-                self.get_neuron(output_neuron).value
-            );
+        for row in 0..rows {
+            self.run_into(
+                &inputs[row * input_stride..(row + 1) * input_stride],
+                &mut outputs[row * output_stride..(row + 1) * output_stride],
+            )?;
         }
 
-        output
+        Ok(())
+    }
+
+    // Clears the memory of recurrent connectors, e.g. between episodes
+    pub fn reset(&mut self) {
+        for neuron in self.neurons.iter_mut() {
+            neuron.value = 0.0;
+            neuron.prev_value = 0.0;
+        }
+    }
+
+    // Inputs and the bias can be changed too, but they default to Identity
+    pub fn set_activation(&mut self, id: usize, activation: Activation) -> Result<(), NetworkError> {
+        let index = *self.neuron_map.get(&id).ok_or(NetworkError::UnknownNeuron(id))?;
+        self.neurons[index].activation = activation;
+
+        Ok(())
+    }
+
+    // Sets every hidden and output neuron at once
+    pub fn set_activations(&mut self, hidden: Activation, output: Activation) {
+        for neuron in self.neurons.iter_mut() {
+            if neuron.id == 0 || self.neuron_levels.0.contains(&neuron.id) {
+                continue;
+            }
+
+            neuron.activation = if self.neuron_levels.1.contains(&neuron.id) { output } else { hidden };
+        }
     }
 
     // ! Eats connector
@@ -0,0 +1,154 @@
+use crate::{Activation, NeuralNetwork, NetworkError};
+
+// Flat execution plan of a NeuralNetwork
+// Neurons get dense indices (their index in NeuralNetwork::neurons) and connectors are stored in firing order,
+// so running it never hashes. Built with NeuralNetwork::compile, the graph stays around for mutation and inspection
+pub struct CompiledNetwork {
+    values: Vec<f64>,
+    prev_values: Vec<f64>,
+    activations: Vec<Activation>,
+
+    bias: usize,
+    inputs: Vec<usize>,
+    outputs: Vec<usize>,
+
+    // Neurons of layer i are layer_neurons[layer_offsets[i]..layer_offsets[i + 1]]
+    layer_neurons: Vec<usize>,
+    layer_offsets: Vec<usize>,
+
+    // Connectors fired after layer i are [connector_offsets[i]..connector_offsets[i + 1]]
+    connector_from: Vec<usize>,
+    connector_to: Vec<usize>,
+    connector_weights: Vec<f64>,
+    connector_offsets: Vec<usize>,
+
+    // (from, to, weight), fired with the values of the previous run
+    recurrent: Vec<(usize, usize, f64)>,
+}
+
+impl NeuralNetwork {
+    pub fn compile(&self) -> CompiledNetwork {
+        let index_of = |id: &usize| *self.neuron_map.get(id).unwrap();
+
+        let mut plan = CompiledNetwork {
+            values: vec![0.0; self.neurons.len()],
+            prev_values: vec![0.0; self.neurons.len()],
+            activations: self.neurons.iter().map(|neuron| neuron.activation).collect(),
+
+            bias: index_of(&0),
+            inputs: self.neuron_levels.0.iter().map(index_of).collect(),
+            outputs: self.neuron_levels.1.iter().map(index_of).collect(),
+
+            layer_neurons: Vec::new(),
+            layer_offsets: vec![0],
+
+            connector_from: Vec::new(),
+            connector_to: Vec::new(),
+            connector_weights: Vec::new(),
+            connector_offsets: vec![0],
+
+            recurrent: Vec::new(),
+        };
+
+        for (layer, set) in self.layers.iter().zip(self.order.iter()) {
+            plan.layer_neurons.extend(layer.iter().map(index_of));
+            plan.layer_offsets.push(plan.layer_neurons.len());
+
+            for connector in set {
+                let connector = &self.connectors[*connector];
+
+                plan.connector_from.push(index_of(&connector.from));
+                plan.connector_to.push(index_of(&connector.to));
+                plan.connector_weights.push(connector.weight);
+            }
+
+            plan.connector_offsets.push(plan.connector_from.len());
+        }
+
+        for connector in self.recurrent.iter() {
+            let connector = &self.connectors[*connector];
+            plan.recurrent.push((index_of(&connector.from), index_of(&connector.to), connector.weight));
+        }
+
+        plan
+    }
+}
+
+impl CompiledNetwork {
+    pub fn run(&mut self, inputs: Vec<f64>) -> Result<Vec<f64>, NetworkError> {
+        let mut output: Vec<f64> = vec![0.0; self.outputs.len()];
+        self.run_into(&inputs, &mut output)?;
+
+        Ok(output)
+    }
+
+    pub fn run_into(&mut self, inputs: &[f64], outputs: &mut [f64]) -> Result<(), NetworkError> {
+        if inputs.len() != self.inputs.len() {
+            return Err(NetworkError::InputCount { expected: self.inputs.len(), got: inputs.len() });
+        }
+
+        if outputs.len() != self.outputs.len() {
+            return Err(NetworkError::OutputCount { expected: self.outputs.len(), got: outputs.len() });
+        }
+
+        self.prev_values.copy_from_slice(&self.values);
+        self.values.fill(0.0);
+
+        self.values[self.bias] = 1.0;
+
+        for (i, input) in self.inputs.iter().enumerate() {
+            self.values[*input] = inputs[i];
+        }
+
+        for (from, to, weight) in self.recurrent.iter() {
+            self.values[*to] += self.prev_values[*from] * weight;
+        }
+
+        for layer in 0..self.layer_offsets.len() - 1 {
+            for neuron in &self.layer_neurons[self.layer_offsets[layer]..self.layer_offsets[layer + 1]] {
+                self.values[*neuron] = self.activations[*neuron].apply(self.values[*neuron]);
+            }
+
+            for connector in self.connector_offsets[layer]..self.connector_offsets[layer + 1] {
+                self.values[self.connector_to[connector]] += self.values[self.connector_from[connector]] * self.connector_weights[connector];
+            }
+        }
+
+        for (i, output) in self.outputs.iter().enumerate() {
+            outputs[i] = self.values[*output];
+        }
+
+        Ok(())
+    }
+
+    // Same layout as NeuralNetwork::run_batch
+    pub fn run_batch(&mut self, inputs: &[f64], outputs: &mut [f64]) -> Result<(), NetworkError> {
+        let input_stride = self.inputs.len();
+        let output_stride = self.outputs.len();
+
+        if output_stride == 0 || !outputs.len().is_multiple_of(output_stride) {
+            return Err(NetworkError::BatchShape);
+        }
+
+        let rows = outputs.len() / output_stride;
+
+        if inputs.len() != rows * input_stride {
+            return Err(NetworkError::BatchShape);
+        }
+
+        for row in 0..rows {
+            self.run_into(
+                &inputs[row * input_stride..(row + 1) * input_stride],
+                &mut outputs[row * output_stride..(row + 1) * output_stride],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    // Clears the memory of recurrent connectors
+    pub fn reset(&mut self) {
+        self.values.fill(0.0);
+        self.prev_values.fill(0.0);
+    }
+}
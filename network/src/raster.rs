@@ -0,0 +1,268 @@
+use crate::{NeuralNetwork, NetworkError};
+use crate::render::{Colour, Role, RADIUS, OUTLINE_COLOUR};
+
+const BACKGROUND_COLOUR: Colour = [0xff, 0xff, 0xff];
+const TEXT_COLOUR: Colour = [0x00, 0x00, 0x00];
+
+const DASH: f64 = 6.0;
+const GAP: f64 = 4.0;
+const ARROW_LENGTH: f64 = 9.0;
+const CURVE_SEGMENTS: usize = 32;
+
+// 3x5 pixel digits, every row uses the low 3 bits with the left pixel as the highest
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+const FONT_SCALE: usize = 2;
+
+impl NeuralNetwork {
+    // Same layout and colours as to_svg, encoded as a png
+    pub fn to_png(&self) -> Result<Vec<u8>, NetworkError> {
+        let (positions, (width, height)) = self.layout();
+        let max_weight = self.max_weight();
+
+        let mut canvas = Canvas::new(width.ceil() as usize, height.ceil() as usize);
+
+        // Edges first so neurons are drawn over them
+        for (i, connector) in self.connectors.iter().enumerate() {
+            let (colour, stroke_width) = self.edge_style(i, max_weight);
+            let points = self.edge_shape(i, &positions).points(CURVE_SEGMENTS);
+
+            canvas.polyline(&points, stroke_width, colour, !connector.enabled);
+            canvas.arrow(&points, colour);
+        }
+
+        let mut neurons: Vec<usize> = self.neurons.iter().map(|neuron| neuron.id).collect();
+        neurons.sort();
+
+        for id in neurons {
+            let role = self.role(id);
+            let (x, y) = positions[&id];
+
+            match role {
+                Role::Bias | Role::Input => {
+                    canvas.fill_rect((x - RADIUS, y - RADIUS), (x + RADIUS, y + RADIUS), OUTLINE_COLOUR);
+                    canvas.fill_rect((x - RADIUS + 1.5, y - RADIUS + 1.5), (x + RADIUS - 1.5, y + RADIUS - 1.5), role.fill());
+                },
+                Role::Hidden | Role::Output => {
+                    let outline = if role == Role::Output { 3.0 } else { 1.5 };
+
+                    canvas.fill_circle((x, y), RADIUS, OUTLINE_COLOUR);
+                    canvas.fill_circle((x, y), RADIUS - outline, role.fill());
+                },
+            }
+
+            canvas.label(&id.to_string(), (x, y), TEXT_COLOUR);
+        }
+
+        canvas.encode()
+    }
+}
+
+// Rgb image, drawn without anti aliasing
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        let mut pixels = Vec::with_capacity(width * height * 3);
+
+        for _ in 0..width * height {
+            pixels.extend_from_slice(&BACKGROUND_COLOUR);
+        }
+
+        Self { width, height, pixels }
+    }
+
+    fn set(&mut self, x: i64, y: i64, colour: Colour) {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+
+        let i = (y as usize * self.width + x as usize) * 3;
+        self.pixels[i..i + 3].copy_from_slice(&colour);
+    }
+
+    // Every pixel whose center passes the test, inside the given bounds
+    fn fill<F>(&mut self, min: (f64, f64), max: (f64, f64), colour: Colour, inside: F) where F: Fn(f64, f64) -> bool {
+        for y in min.1.floor() as i64..=max.1.ceil() as i64 {
+            for x in min.0.floor() as i64..=max.0.ceil() as i64 {
+                if inside(x as f64 + 0.5, y as f64 + 0.5) {
+                    self.set(x, y, colour);
+                }
+            }
+        }
+    }
+
+    fn fill_rect(&mut self, min: (f64, f64), max: (f64, f64), colour: Colour) {
+        self.fill(min, max, colour, |x, y| x >= min.0 && x <= max.0 && y >= min.1 && y <= max.1);
+    }
+
+    fn fill_circle(&mut self, center: (f64, f64), radius: f64, colour: Colour) {
+        self.fill(
+            (center.0 - radius, center.1 - radius),
+            (center.0 + radius, center.1 + radius),
+            colour,
+            |x, y| (x - center.0).powi(2) + (y - center.1).powi(2) <= radius * radius,
+        );
+    }
+
+    fn line(&mut self, start: (f64, f64), end: (f64, f64), width: f64, colour: Colour) {
+        let half = (width / 2.0).max(0.5);
+
+        self.fill(
+            (start.0.min(end.0) - half, start.1.min(end.1) - half),
+            (start.0.max(end.0) + half, start.1.max(end.1) + half),
+            colour,
+            |x, y| distance_to_segment((x, y), start, end) <= half,
+        );
+    }
+
+    // Dashes carry on across the points, so curves get an even pattern
+    fn polyline(&mut self, points: &[(f64, f64)], width: f64, colour: Colour, dashed: bool) {
+        let mut travelled = 0.0;
+
+        for pair in points.windows(2) {
+            let (start, end) = (pair[0], pair[1]);
+            let length = ((end.0 - start.0).powi(2) + (end.1 - start.1).powi(2)).sqrt();
+
+            if !dashed {
+                self.line(start, end, width, colour);
+                continue;
+            }
+
+            let mut position = 0.0;
+
+            while position < length {
+                let phase = (travelled + position) % (DASH + GAP);
+
+                let next = if phase < DASH {
+                    let next = length.min(position + DASH - phase);
+                    self.line(lerp(start, end, position / length), lerp(start, end, next / length), width, colour);
+
+                    next
+                } else {
+                    length.min(position + DASH + GAP - phase)
+                };
+
+                position = next;
+            }
+
+            travelled += length;
+        }
+    }
+
+    // Arrow head at the last point, pointing along the last segment
+    fn arrow(&mut self, points: &[(f64, f64)], colour: Colour) {
+        let (tip, previous) = match points {
+            [.., previous, tip] => (*tip, *previous),
+            _ => return,
+        };
+
+        let (dx, dy) = (tip.0 - previous.0, tip.1 - previous.1);
+        let length = (dx * dx + dy * dy).sqrt();
+
+        if length == 0.0 {
+            return;
+        }
+
+        let (dx, dy) = (dx / length, dy / length);
+        let base = (tip.0 - dx * ARROW_LENGTH, tip.1 - dy * ARROW_LENGTH);
+        let side = (-dy * ARROW_LENGTH / 2.0, dx * ARROW_LENGTH / 2.0);
+
+        let corners = [tip, (base.0 + side.0, base.1 + side.1), (base.0 - side.0, base.1 - side.1)];
+
+        let min = (corners.iter().map(|c| c.0).fold(f64::INFINITY, f64::min), corners.iter().map(|c| c.1).fold(f64::INFINITY, f64::min));
+        let max = (corners.iter().map(|c| c.0).fold(f64::NEG_INFINITY, f64::max), corners.iter().map(|c| c.1).fold(f64::NEG_INFINITY, f64::max));
+
+        self.fill(min, max, colour, |x, y| {
+            // Inside when the point is on the same side of every edge
+            let sides: Vec<f64> = (0..3)
+                .map(|i| {
+                    let (a, b) = (corners[i], corners[(i + 1) % 3]);
+                    (b.0 - a.0) * (y - a.1) - (b.1 - a.1) * (x - a.0)
+                })
+                .collect();
+
+            sides.iter().all(|side| *side >= 0.0) || sides.iter().all(|side| *side <= 0.0)
+        });
+    }
+
+    // Only digits are drawn, which is all neuron labels need
+    fn label(&mut self, text: &str, center: (f64, f64), colour: Colour) {
+        let digits: Vec<usize> = text.chars().filter_map(|c| c.to_digit(10)).map(|d| d as usize).collect();
+
+        let glyph_width = 3 * FONT_SCALE;
+        let spacing = FONT_SCALE;
+        let text_width = digits.len() * glyph_width + digits.len().saturating_sub(1) * spacing;
+
+        let left = (center.0 - text_width as f64 / 2.0).round() as i64;
+        let top = (center.1 - (5 * FONT_SCALE) as f64 / 2.0).round() as i64;
+
+        for (i, digit) in digits.into_iter().enumerate() {
+            let x = left + (i * (glyph_width + spacing)) as i64;
+
+            for (row, bits) in DIGITS[digit].iter().enumerate() {
+                for column in 0..3 {
+                    if bits & (0b100 >> column) == 0 {
+                        continue;
+                    }
+
+                    for dy in 0..FONT_SCALE {
+                        for dx in 0..FONT_SCALE {
+                            self.set(
+                                x + (column * FONT_SCALE + dx) as i64,
+                                top + (row * FONT_SCALE + dy) as i64,
+                                colour,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn encode(&self) -> Result<Vec<u8>, NetworkError> {
+        let mut png = Vec::new();
+
+        let mut encoder = png::Encoder::new(&mut png, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(|error| NetworkError::Encoding(error.to_string()))?;
+        writer.write_image_data(&self.pixels).map_err(|error| NetworkError::Encoding(error.to_string()))?;
+        writer.finish().map_err(|error| NetworkError::Encoding(error.to_string()))?;
+
+        Ok(png)
+    }
+}
+
+fn lerp(start: (f64, f64), end: (f64, f64), t: f64) -> (f64, f64) {
+    (start.0 + (end.0 - start.0) * t, start.1 + (end.1 - start.1) * t)
+}
+
+fn distance_to_segment(point: (f64, f64), start: (f64, f64), end: (f64, f64)) -> f64 {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length_squared = dx * dx + dy * dy;
+
+    let t = if length_squared == 0.0 {
+        0.0
+    } else {
+        (((point.0 - start.0) * dx + (point.1 - start.1) * dy) / length_squared).clamp(0.0, 1.0)
+    };
+
+    let closest = lerp(start, end, t);
+    ((point.0 - closest.0).powi(2) + (point.1 - closest.1).powi(2)).sqrt()
+}
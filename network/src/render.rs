@@ -0,0 +1,323 @@
+use crate::NeuralNetwork;
+
+use std::collections::HashMap;
+
+// Spacing of the svg and png layout, in pixels
+const COLUMN_SPACING: f64 = 140.0;
+const ROW_SPACING: f64 = 70.0;
+const MARGIN: f64 = 50.0;
+pub(crate) const RADIUS: f64 = 16.0;
+
+pub(crate) type Colour = [u8; 3];
+
+const POSITIVE_COLOUR: Colour = [0x2e, 0x8b, 0x57];
+const NEGATIVE_COLOUR: Colour = [0xc0, 0x39, 0x2b];
+const DISABLED_COLOUR: Colour = [0x9e, 0x9e, 0x9e];
+pub(crate) const OUTLINE_COLOUR: Colour = [0x33, 0x33, 0x33];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Role {
+    Bias,
+    Input,
+    Hidden,
+    Output,
+}
+
+// Where an edge goes, shared by the svg and png renderers
+pub(crate) enum EdgeShape {
+    Line((f64, f64), (f64, f64)),
+    Quadratic((f64, f64), (f64, f64), (f64, f64)),
+    Cubic((f64, f64), (f64, f64), (f64, f64), (f64, f64)),
+}
+
+impl EdgeShape {
+    fn to_svg_path(&self) -> String {
+        match self {
+            EdgeShape::Line(start, end) => format!("M {:.1} {:.1} L {:.1} {:.1}", start.0, start.1, end.0, end.1),
+            EdgeShape::Quadratic(start, control, end) => format!(
+                "M {:.1} {:.1} Q {:.1} {:.1} {:.1} {:.1}",
+                start.0, start.1, control.0, control.1, end.0, end.1,
+            ),
+            EdgeShape::Cubic(start, control_1, control_2, end) => format!(
+                "M {:.1} {:.1} C {:.1} {:.1} {:.1} {:.1} {:.1} {:.1}",
+                start.0, start.1, control_1.0, control_1.1, control_2.0, control_2.1, end.0, end.1,
+            ),
+        }
+    }
+
+    // Curves are flattened into that many straight segments
+    pub(crate) fn points(&self, segments: usize) -> Vec<(f64, f64)> {
+        match self {
+            EdgeShape::Line(start, end) => vec![*start, *end],
+            EdgeShape::Quadratic(start, control, end) => (0..=segments)
+                .map(|i| {
+                    let t = i as f64 / segments as f64;
+                    let u = 1.0 - t;
+
+                    (
+                        u * u * start.0 + 2.0 * u * t * control.0 + t * t * end.0,
+                        u * u * start.1 + 2.0 * u * t * control.1 + t * t * end.1,
+                    )
+                })
+                .collect(),
+            EdgeShape::Cubic(start, control_1, control_2, end) => (0..=segments)
+                .map(|i| {
+                    let t = i as f64 / segments as f64;
+                    let u = 1.0 - t;
+
+                    (
+                        u * u * u * start.0 + 3.0 * u * u * t * control_1.0 + 3.0 * u * t * t * control_2.0 + t * t * t * end.0,
+                        u * u * u * start.1 + 3.0 * u * u * t * control_1.1 + 3.0 * u * t * t * control_2.1 + t * t * t * end.1,
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Role {
+    pub(crate) fn fill(self) -> Colour {
+        match self {
+            Role::Bias => [0xf4, 0xd3, 0x5e],
+            Role::Input => [0x8c, 0xb6, 0xfa],
+            Role::Hidden => [0xd9, 0xd9, 0xd9],
+            Role::Output => [0xf7, 0x92, 0x56],
+        }
+    }
+
+    fn dot_shape(self) -> &'static str {
+        match self {
+            Role::Bias => "box",
+            Role::Input => "box",
+            Role::Hidden => "circle",
+            Role::Output => "doublecircle",
+        }
+    }
+}
+
+impl NeuralNetwork {
+    pub fn role(&self, id: usize) -> Role {
+        if id == 0 {
+            Role::Bias
+        } else if self.neuron_levels.0.contains(&id) {
+            Role::Input
+        } else if self.neuron_levels.1.contains(&id) {
+            Role::Output
+        } else {
+            Role::Hidden
+        }
+    }
+
+    // Columns follow the layers, but the bias and inputs always sit in the first column and outputs in the last one
+    // Neurons are sorted by id inside a column, so the same network always gives the same picture
+    // Returns neuron id -> (x, y) and the (width, height) of the picture
+    pub fn layout(&self) -> (HashMap<usize, (f64, f64)>, (f64, f64)) {
+        let mut hidden_columns: Vec<(usize, usize)> = Vec::new();
+
+        for (i, layer) in self.layers.iter().enumerate() {
+            for id in layer {
+                if self.role(*id) == Role::Hidden {
+                    hidden_columns.push((*id, i.max(1)));
+                }
+            }
+        }
+
+        let output_column = hidden_columns.iter().map(|(_, column)| column + 1).max().unwrap_or(1);
+
+        let mut columns: Vec<Vec<usize>> = vec![Vec::new(); output_column + 1];
+        columns[0].push(0);
+        columns[0].extend(&self.neuron_levels.0);
+        columns[output_column].extend(&self.neuron_levels.1);
+
+        for (id, column) in hidden_columns {
+            columns[column].push(id);
+        }
+
+        // Layering can leave a column empty, e.g. when hidden neurons only feed each other
+        columns.retain(|column| !column.is_empty());
+
+        let rows = columns.iter().map(|column| column.len()).max().unwrap_or(1);
+        let height = 2.0 * MARGIN + (rows - 1) as f64 * ROW_SPACING;
+        let width = 2.0 * MARGIN + (columns.len() - 1) as f64 * COLUMN_SPACING;
+
+        let mut positions: HashMap<usize, (f64, f64)> = HashMap::new();
+
+        for (i, column) in columns.iter_mut().enumerate() {
+            column.sort();
+
+            // Centered vertically
+            let offset = (height - (column.len() - 1) as f64 * ROW_SPACING) / 2.0;
+
+            for (j, id) in column.iter().enumerate() {
+                positions.insert(*id, (MARGIN + i as f64 * COLUMN_SPACING, offset + j as f64 * ROW_SPACING));
+            }
+        }
+
+        (positions, (width, height))
+    }
+
+    // Largest absolute weight, edge widths are scaled against it
+    pub(crate) fn max_weight(&self) -> f64 {
+        self.connectors.iter()
+            .filter(|connector| connector.enabled)
+            .map(|connector| connector.weight.abs())
+            .fold(0.0, f64::max)
+    }
+
+    // (colour, width) of a connector
+    pub(crate) fn edge_style(&self, connector: usize, max_weight: f64) -> (Colour, f64) {
+        let connector = &self.connectors[connector];
+
+        if !connector.enabled {
+            return (DISABLED_COLOUR, 1.0);
+        }
+
+        let colour = if connector.weight < 0.0 { NEGATIVE_COLOUR } else { POSITIVE_COLOUR };
+        let width = if max_weight > 0.0 { 1.0 + 4.0 * connector.weight.abs() / max_weight } else { 1.0 };
+
+        (colour, width)
+    }
+
+    pub(crate) fn edge_shape(&self, index: usize, positions: &HashMap<usize, (f64, f64)>) -> EdgeShape {
+        let connector = &self.connectors[index];
+
+        let (x1, y1) = positions[&connector.from];
+        let (x2, y2) = positions[&connector.to];
+
+        if connector.from == connector.to {
+            // Self loop above the neuron
+            EdgeShape::Cubic(
+                (x1 - RADIUS * 0.6, y1 - RADIUS * 0.8),
+                (x1 - RADIUS * 2.0, y1 - RADIUS * 3.5),
+                (x1 + RADIUS * 2.0, y1 - RADIUS * 3.5),
+                (x1 + RADIUS * 0.6, y1 - RADIUS * 0.8),
+            )
+        } else if self.recurrent.contains(&index) {
+            // Back edges bend away from the forward ones
+            let control = ((x1 + x2) / 2.0 + (y2 - y1) * 0.3, (y1 + y2) / 2.0 - (x2 - x1) * 0.3 - RADIUS * 2.0);
+
+            EdgeShape::Quadratic(towards((x1, y1), control, RADIUS), control, towards((x2, y2), control, RADIUS))
+        } else {
+            EdgeShape::Line(towards((x1, y1), (x2, y2), RADIUS), towards((x2, y2), (x1, y1), RADIUS))
+        }
+    }
+
+    // Graphviz source, render it with `dot -Tsvg network.dot -o network.svg`
+    pub fn to_dot(&self) -> String {
+        let max_weight = self.max_weight();
+        let mut dot = String::from("digraph network {\n    rankdir=LR;\n    node [style=filled, fontname=\"Helvetica\"];\n\n");
+
+        let mut inputs = vec![0];
+        inputs.extend(&self.neuron_levels.0);
+
+        dot.push_str(&format!("    {{ rank=source; {} }}\n", join(&inputs)));
+        dot.push_str(&format!("    {{ rank=sink; {} }}\n\n", join(&self.neuron_levels.1)));
+
+        let mut neurons: Vec<usize> = self.neurons.iter().map(|neuron| neuron.id).collect();
+        neurons.sort();
+
+        for id in neurons {
+            let role = self.role(id);
+            dot.push_str(&format!("    {} [label=\"{}\", shape={}, fillcolor=\"{}\"];\n", id, id, role.dot_shape(), hex(role.fill())));
+        }
+
+        dot.push('\n');
+
+        for (i, connector) in self.connectors.iter().enumerate() {
+            let (colour, width) = self.edge_style(i, max_weight);
+            let style = if connector.enabled { "solid" } else { "dashed" };
+
+            dot.push_str(&format!(
+                "    {} -> {} [color=\"{}\", penwidth={:.2}, style={}, tooltip=\"{:.4}\"];\n",
+                connector.from, connector.to, hex(colour), width, style, connector.weight,
+            ));
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    // Standalone svg, laid out with layout
+    pub fn to_svg(&self) -> String {
+        let (positions, (width, height)) = self.layout();
+        let max_weight = self.max_weight();
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" viewBox=\"0 0 {:.0} {:.0}\" font-family=\"Helvetica, sans-serif\" font-size=\"11\">\n",
+            width, height, width, height,
+        );
+
+        svg.push_str("  <defs>\n");
+
+        for (name, colour) in [("positive", POSITIVE_COLOUR), ("negative", NEGATIVE_COLOUR), ("disabled", DISABLED_COLOUR)] {
+            svg.push_str(&format!(
+                "    <marker id=\"arrow-{}\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"6\" markerHeight=\"6\" markerUnits=\"userSpaceOnUse\" orient=\"auto\"><path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"{}\"/></marker>\n",
+                name, hex(colour),
+            ));
+        }
+
+        svg.push_str("  </defs>\n");
+        svg.push_str(&format!("  <rect width=\"{:.0}\" height=\"{:.0}\" fill=\"white\"/>\n", width, height));
+
+        // Edges first so neurons are drawn over them
+        for (i, connector) in self.connectors.iter().enumerate() {
+            let (colour, stroke_width) = self.edge_style(i, max_weight);
+            let marker = if !connector.enabled { "disabled" } else if connector.weight < 0.0 { "negative" } else { "positive" };
+            let dash = if connector.enabled { "" } else { " stroke-dasharray=\"6 4\"" };
+
+            let path = self.edge_shape(i, &positions).to_svg_path();
+
+            svg.push_str(&format!(
+                "  <path d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{:.2}\"{} marker-end=\"url(#arrow-{})\"><title>{} -> {}: {:.4}</title></path>\n",
+                path, hex(colour), stroke_width, dash, marker, connector.from, connector.to, connector.weight,
+            ));
+        }
+
+        let mut neurons: Vec<usize> = self.neurons.iter().map(|neuron| neuron.id).collect();
+        neurons.sort();
+
+        for id in neurons {
+            let role = self.role(id);
+            let (x, y) = positions[&id];
+
+            let shape = match role {
+                Role::Bias | Role::Input => format!(
+                    "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\" stroke=\"{}\"/>",
+                    x - RADIUS, y - RADIUS, RADIUS * 2.0, RADIUS * 2.0, hex(role.fill()), hex(OUTLINE_COLOUR),
+                ),
+                Role::Hidden | Role::Output => format!(
+                    "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" fill=\"{}\" stroke=\"{}\"{}/>",
+                    x, y, RADIUS, hex(role.fill()), hex(OUTLINE_COLOUR), if role == Role::Output { " stroke-width=\"3\"" } else { "" },
+                ),
+            };
+
+            svg.push_str(&format!(
+                "  <g>{}<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text></g>\n",
+                shape, x, y, id,
+            ));
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+// Point on the edge of the neuron at from, in the direction of to
+fn towards(from: (f64, f64), to: (f64, f64), distance: f64) -> (f64, f64) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length = (dx * dx + dy * dy).sqrt();
+
+    if length == 0.0 {
+        return from;
+    }
+
+    (from.0 + dx / length * distance, from.1 + dy / length * distance)
+}
+
+fn hex(colour: Colour) -> String {
+    format!("#{:02x}{:02x}{:02x}", colour[0], colour[1], colour[2])
+}
+
+fn join(ids: &[usize]) -> String {
+    ids.iter().map(|id| id.to_string()).collect::<Vec<String>>().join("; ")
+}
@@ -2,7 +2,7 @@ extern crate network;
 use innovation::*;
 use network::*;
 
-fn sort_layers(mut layers: Vec<Vec<usize>>) -> Vec<Vec<usize>> {
+fn sort_layers(layers: Vec<Vec<usize>>) -> Vec<Vec<usize>> {
     let mut sorted_layers: Vec<Vec<usize>> = Vec::new();
 
     for layer in &layers {
@@ -18,7 +18,7 @@ fn sort_layers(mut layers: Vec<Vec<usize>>) -> Vec<Vec<usize>> {
 #[test]
 fn layering() {
     // ! Network 1 - Simple
-    let genome1 = Genome(vec![0, 1, 2], vec![0.1, 0.2, 0.3], vec![true, true, true]);
+    let genome1 = Genome::try_from((vec![0, 1, 2], vec![0.1, 0.2, 0.3], vec![true, true, true])).unwrap();
 
     let table1 = InnovationTable::init(
         (vec![1], vec![2]),
@@ -27,17 +27,18 @@ fn layering() {
            (0, 2, Type::Connector),
            (0, 3, Type::Connector),
         ]
-    );
+    ).unwrap();
 
-    let network = NeuralNetwork::init(&genome1, &table1);
+    let network = NeuralNetwork::init(&genome1, &table1).unwrap();
 
+    // Neuron 3 is only fed by the bias and feeds nothing, so it ends up in the last layer with the output
     let network_sorted = sort_layers(network.layers);
-    let actual_sorted = sort_layers(vec![vec![0, 1], vec![4], vec![3, 5], vec![2]]);
+    let actual_sorted = sort_layers(vec![vec![0, 1], vec![2, 3]]);
 
     assert_eq!(network_sorted, actual_sorted);
 
     // ! Network 2 - Advanced
-    let genome = Genome(vec![0, 1, 2, 3, 4, 5], vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6], vec![true, true, true, true, true, true]);
+    let genome = Genome::try_from((vec![0, 1, 2, 3, 4, 5], vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6], vec![true, true, true, true, true, true])).unwrap();
 
     let table = InnovationTable::init(
         (vec![1], vec![2]),
@@ -49,9 +50,9 @@ fn layering() {
            (0, 5, Type::Connector),
            (0, 3, Type::Connector),
         ]
-    );
+    ).unwrap();
 
-    let network = NeuralNetwork::init(&genome, &table);
+    let network = NeuralNetwork::init(&genome, &table).unwrap();
     
     let network_sorted = sort_layers(network.layers);
     let actual_sorted = sort_layers(vec![vec![0, 1], vec![4], vec![3, 5], vec![2]]);
@@ -59,7 +60,7 @@ fn layering() {
     assert_eq!(network_sorted, actual_sorted);
 
     // ! Network 3 - Neuron Simple
-    let genome = Genome(vec![0, 1, 2], vec![1.1, 1.1, 1.1], vec![true, true, true]);
+    let genome = Genome::try_from((vec![0, 1, 2], vec![1.1, 1.1, 1.1], vec![true, true, true])).unwrap();
 
     let table = InnovationTable::init(
         (vec![1], vec![2]),
@@ -68,12 +69,359 @@ fn layering() {
             (1, 3, Type::Connector),
             (3, 2, Type::Connector),
         ]
-    );
+    ).unwrap();
 
-    let network = NeuralNetwork::init(&genome, &table);
+    let network = NeuralNetwork::init(&genome, &table).unwrap();
 
     let network_sorted = sort_layers(network.layers);
     let actual_sorted = sort_layers(vec![vec![0, 1], vec![3], vec![2]]);
 
     assert_eq!(network_sorted, actual_sorted);
-}
\ No newline at end of file
+}
+
+#[test]
+fn run() {
+    // Hidden neuron 3 has an id larger than the number of neurons before it, so it has to be looked up through the map
+    let genome = Genome::try_from((vec![0, 1], vec![0.5, 2.0], vec![true, true])).unwrap();
+
+    let table = InnovationTable::init(
+        (vec![1], vec![2]),
+        vec![
+            (1, 3, Type::Connector),
+            (3, 2, Type::Connector),
+        ]
+    ).unwrap();
+
+    let mut network = NeuralNetwork::init(&genome, &table).unwrap();
+    network.set_activation(3, Activation::Identity).unwrap();
+
+    assert_eq!(network.run(vec![3.0]).unwrap(), vec![3.0]);
+}
+
+#[test]
+fn xor() {
+    // Hidden 4 is OR, hidden 5 is NAND, the output adds them and subtracts 1
+    let genome = Genome::try_from((
+        (0..9).collect(),
+        vec![20.0, 20.0, -10.0, -20.0, -20.0, 30.0, 1.0, 1.0, -1.0],
+        vec![true; 9],
+    )).unwrap();
+
+    let table = InnovationTable::init(
+        (vec![1, 2], vec![3]),
+        vec![
+            (1, 4, Type::Connector),
+            (2, 4, Type::Connector),
+            (0, 4, Type::Connector),
+            (1, 5, Type::Connector),
+            (2, 5, Type::Connector),
+            (0, 5, Type::Connector),
+            (4, 3, Type::Connector),
+            (5, 3, Type::Connector),
+            (0, 3, Type::Connector),
+        ]
+    ).unwrap();
+
+    let mut network = NeuralNetwork::init(&genome, &table).unwrap();
+    network.set_activations(Activation::Sigmoid, Activation::Identity);
+
+    for (inputs, expected) in [([0.0, 0.0], 0.0), ([0.0, 1.0], 1.0), ([1.0, 0.0], 1.0), ([1.0, 1.0], 0.0)] {
+        let output = network.run(inputs.to_vec()).unwrap()[0];
+        assert!((output - expected).abs() < 0.01, "{:?} gave {}", inputs, output);
+    }
+
+    assert_eq!(Activation::Relu.apply(-1.0), 0.0);
+    assert_eq!(Activation::Step.apply(0.5), 1.0);
+    assert!((Activation::SteepenedSigmoid.apply(0.0) - 0.5).abs() < 1e-12);
+}
+
+#[test]
+fn recurrent() {
+    // Hidden 3 feeds itself, 3 and 4 form a loop
+    let genome = Genome::try_from((vec![0, 1, 2, 3, 4], vec![1.0, 0.5, 1.0, 1.0, 0.0], vec![true; 5])).unwrap();
+
+    let table = InnovationTable::init(
+        (vec![1], vec![2]),
+        vec![
+            (1, 3, Type::Connector),
+            (3, 3, Type::Connector),
+            (3, 2, Type::Connector),
+            (3, 4, Type::Connector),
+            (4, 3, Type::Connector),
+        ]
+    ).unwrap();
+
+    let mut network = NeuralNetwork::init(&genome, &table).unwrap();
+    network.set_activations(Activation::Identity, Activation::Identity);
+
+    let mut recurrent: Vec<(usize, usize)> = network.recurrent.iter()
+        .map(|i| (network.connectors[*i].from, network.connectors[*i].to))
+        .collect();
+    recurrent.sort();
+
+    assert_eq!(recurrent, vec![(3, 3), (4, 3)]);
+
+    assert_eq!(network.run(vec![1.0]).unwrap(), vec![1.0]);
+    assert_eq!(network.run(vec![1.0]).unwrap(), vec![1.5]);
+    assert_eq!(network.run(vec![1.0]).unwrap(), vec![1.75]);
+
+    network.reset();
+    assert_eq!(network.run(vec![1.0]).unwrap(), vec![1.0]);
+}
+
+#[test]
+fn gradients() {
+    let genome = Genome::try_from((vec![0, 1, 2, 3, 4], vec![0.3, -0.6, 0.8, 0.5, -0.2], vec![true; 5])).unwrap();
+
+    let table = InnovationTable::init(
+        (vec![1, 2], vec![3]),
+        vec![
+            (1, 4, Type::Connector),
+            (2, 4, Type::Connector),
+            (0, 4, Type::Connector),
+            (4, 3, Type::Connector),
+            (1, 3, Type::Connector),
+        ]
+    ).unwrap();
+
+    let mut network = NeuralNetwork::init(&genome, &table).unwrap();
+    network.set_activations(Activation::Tanh, Activation::Sigmoid);
+
+    let inputs = vec![0.7, -0.4];
+    let targets = [0.9];
+
+    let (gradients, _) = network.gradients(inputs.clone(), &targets).unwrap();
+
+    // Compare against finite differences
+    for (i, gradient) in gradients.iter().enumerate() {
+        let original = network.connectors[i].weight;
+        let epsilon = 1e-6;
+
+        network.connectors[i].weight = original + epsilon;
+        let (_, loss_plus) = network.gradients(inputs.clone(), &targets).unwrap();
+
+        network.connectors[i].weight = original - epsilon;
+        let (_, loss_minus) = network.gradients(inputs.clone(), &targets).unwrap();
+
+        network.connectors[i].weight = original;
+
+        let numeric = (loss_plus - loss_minus) / (2.0 * epsilon);
+        assert!((numeric - gradient).abs() < 1e-6, "connector {}: {} vs {}", i, numeric, gradient);
+    }
+
+    // A few steps of descent lower the loss
+    let (_, first_loss) = network.gradients(inputs.clone(), &targets).unwrap();
+
+    for _ in 0..50 {
+        let (gradients, _) = network.gradients(inputs.clone(), &targets).unwrap();
+        network.apply_gradients(&gradients, 0.5);
+    }
+
+    let (_, last_loss) = network.gradients(inputs.clone(), &targets).unwrap();
+    assert!(last_loss < first_loss);
+}
+
+#[test]
+fn run_batch() {
+    let genome = Genome::try_from((vec![0, 1, 2], vec![0.5, -1.0, 2.0], vec![true; 3])).unwrap();
+
+    let table = InnovationTable::init(
+        (vec![1, 2], vec![3]),
+        vec![
+            (1, 3, Type::Connector),
+            (2, 3, Type::Connector),
+            (0, 3, Type::Connector),
+        ]
+    ).unwrap();
+
+    let mut network = NeuralNetwork::init(&genome, &table).unwrap();
+
+    let inputs = [1.0, 2.0, 0.0, 0.0, -2.0, 4.0];
+    let mut outputs = [0.0; 3];
+
+    network.run_batch(&inputs, &mut outputs).unwrap();
+
+    for row in 0..3 {
+        assert_eq!(outputs[row], network.run(inputs[row * 2..row * 2 + 2].to_vec()).unwrap()[0]);
+    }
+
+    assert_eq!(outputs, [0.5, 2.0, -3.0]);
+}
+
+#[test]
+fn compile() {
+    // Same shape as the recurrent test, plus a second input
+    let genome = Genome::try_from((vec![0, 1, 2, 3, 4, 5, 6], vec![1.0, 0.5, 1.0, 0.3, -0.7, 0.2, 0.9], vec![true; 7])).unwrap();
+
+    let table = InnovationTable::init(
+        (vec![1, 2], vec![3]),
+        vec![
+            (1, 4, Type::Connector),
+            (4, 4, Type::Connector),
+            (4, 3, Type::Connector),
+            (4, 5, Type::Connector),
+            (5, 4, Type::Connector),
+            (2, 5, Type::Connector),
+            (0, 3, Type::Connector),
+        ]
+    ).unwrap();
+
+    let mut network = NeuralNetwork::init(&genome, &table).unwrap();
+    network.set_activation(5, Activation::Tanh).unwrap();
+
+    let mut plan = network.compile();
+
+    for inputs in [[1.0, 0.0], [0.5, -0.5], [-1.0, 2.0], [0.0, 0.0]] {
+        assert_eq!(plan.run(inputs.to_vec()).unwrap(), network.run(inputs.to_vec()).unwrap());
+    }
+
+    network.reset();
+    plan.reset();
+
+    let inputs = [1.0, 2.0, 3.0, 4.0];
+    let mut plan_outputs = [0.0; 2];
+    let mut network_outputs = [0.0; 2];
+
+    plan.run_batch(&inputs, &mut plan_outputs).unwrap();
+    network.run_batch(&inputs, &mut network_outputs).unwrap();
+
+    assert_eq!(plan_outputs, network_outputs);
+}
+
+#[test]
+fn errors() {
+    let table = InnovationTable::init(
+        (vec![1], vec![2]),
+        vec![
+            (1, 2, Type::Connector),
+            (2, 1, Type::Connector),
+        ]
+    ).unwrap();
+
+    // Gene 5 isnt in the table
+    let genome = Genome::try_from((vec![0, 5], vec![1.0, 1.0], vec![true, true])).unwrap();
+    assert_eq!(NeuralNetwork::init(&genome, &table).err(), Some(NetworkError::UnknownGene(5)));
+
+    assert_eq!(Genome::try_from((vec![0], vec![1.0, 1.0], vec![true])).err(), Some(NetworkError::GenomeLengthMismatch));
+
+    let genome = Genome::try_from((vec![1], vec![1.0], vec![true])).unwrap();
+    assert_eq!(NeuralNetwork::init(&genome, &table).err(), Some(NetworkError::InputConnected(1)));
+
+    let genome = Genome::try_from((vec![0], vec![1.0], vec![true])).unwrap();
+    let mut network = NeuralNetwork::init(&genome, &table).unwrap();
+
+    assert_eq!(network.run(vec![1.0, 2.0]).err(), Some(NetworkError::InputCount { expected: 1, got: 2 }));
+    assert_eq!(network.run_batch(&[1.0, 2.0], &mut [0.0; 3]).err(), Some(NetworkError::BatchShape));
+    assert_eq!(network.set_activation(9, Activation::Relu).err(), Some(NetworkError::UnknownNeuron(9)));
+
+    assert_eq!(
+        InnovationTable::init((vec![1], vec![2]), vec![(1, 2, Type::Connector), (1, 2, Type::Connector)]).err(),
+        Some(InnovationError::Duplicate((1, 2, Type::Connector)))
+    );
+    assert_eq!(InnovationTable::init((vec![2], vec![3]), vec![]).err(), Some(InnovationError::InputsNotSequential));
+}
+
+#[test]
+fn genome() {
+    let mut genome = Genome::try_from((vec![4, 0, 2], vec![0.4, 0.0, 0.2], vec![true, true, false])).unwrap();
+
+    // Always in innovation order
+    let innovations: Vec<usize> = genome.iter().map(|gene| gene.innovation).collect();
+    assert_eq!(innovations, vec![0, 2, 4]);
+
+    assert_eq!(genome.insert(Gene::new(3, 0.3, true)), None);
+    assert_eq!(genome.insert(Gene::new(4, -0.4, true)), Some(Gene::new(4, 0.4, true)));
+
+    assert_eq!(genome.len(), 4);
+    assert_eq!(genome.max_innovation(), Some(4));
+    assert_eq!(genome.find_weight(4), Some(-0.4));
+
+    assert!(genome.enable(2));
+    assert!(genome.disable(0));
+    assert!(!genome.disable(9));
+
+    assert_eq!(genome.remove(3), Some(Gene::new(3, 0.3, true)));
+    assert!(!genome.contains(3));
+
+    let tuple: (Vec<usize>, Vec<f64>, Vec<bool>) = genome.into();
+    assert_eq!(tuple, (vec![0, 2, 4], vec![0.0, 0.2, -0.4], vec![false, true, true]));
+}
+
+#[test]
+fn render() {
+    // 1 -> 4 -> 3, with a disabled 2 -> 3 and the bias on the output
+    let genome = Genome::try_from((vec![0, 1, 2, 3], vec![0.5, -1.0, 0.7, 0.25], vec![true, true, false, true])).unwrap();
+
+    let table = InnovationTable::init(
+        (vec![1, 2], vec![3]),
+        vec![
+            (1, 4, Type::Connector),
+            (4, 3, Type::Connector),
+            (2, 3, Type::Connector),
+            (0, 3, Type::Connector),
+        ]
+    ).unwrap();
+
+    let network = NeuralNetwork::init(&genome, &table).unwrap();
+
+    assert_eq!(network.role(0), Role::Bias);
+    assert_eq!(network.role(2), Role::Input);
+    assert_eq!(network.role(3), Role::Output);
+    assert_eq!(network.role(4), Role::Hidden);
+
+    // Inputs, then the hidden neuron, then the output
+    let (positions, _) = network.layout();
+    assert!(positions[&1].0 < positions[&4].0 && positions[&4].0 < positions[&3].0);
+    assert_eq!(positions[&0].0, positions[&2].0);
+
+    let dot = network.to_dot();
+    assert!(dot.starts_with("digraph network {"));
+    assert!(dot.contains("3 [label=\"3\", shape=doublecircle"));
+    assert!(dot.contains("4 -> 3 [color=\"#c0392b\", penwidth=5.00, style=solid"));
+    assert!(dot.contains("2 -> 3 [color=\"#9e9e9e\", penwidth=1.00, style=dashed"));
+
+    let svg = network.to_svg();
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert_eq!(svg.matches("<path d=").count() - 3, 4); // Minus the three arrow heads
+    assert_eq!(svg.matches("stroke-dasharray").count(), 1);
+
+    // Same network, same picture
+    assert_eq!(svg, NeuralNetwork::init(&genome, &table).unwrap().to_svg());
+
+    let png = network.to_png().unwrap();
+    let (_, (width, height)) = network.layout();
+
+    assert_eq!(&png[..8], &[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a]);
+    assert_eq!(&png[12..16], b"IHDR");
+    assert_eq!(u32::from_be_bytes(png[16..20].try_into().unwrap()), width as u32);
+    assert_eq!(u32::from_be_bytes(png[20..24].try_into().unwrap()), height as u32);
+    assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+
+    assert_eq!(png, NeuralNetwork::init(&genome, &table).unwrap().to_png().unwrap());
+}
+
+#[test]
+fn splits() {
+    let mut table = InnovationTable::init((vec![1, 2], vec![3]), vec![(1, 3, Type::Connector), (2, 3, Type::Connector)]).unwrap();
+
+    // Same connection, same neuron
+    let neuron = table.split_connection(1, 3, |_| false).unwrap();
+    assert_eq!(neuron, 4);
+    assert_eq!(table.split_connection(1, 3, |_| false).unwrap(), neuron);
+    assert_eq!(table.split_connection(2, 3, |_| false).unwrap(), 5);
+
+    // The split itself is an innovation, which networks skip
+    let split = *table.get_innovation((1, 3, Type::Neuron)).unwrap();
+    assert_eq!(table.split_neurons[&split], vec![neuron]);
+
+    // A genome that already has neuron 4 gets a new neuron, and the next genome with the same conflict gets that one too
+    assert_eq!(table.split_connection(1, 3, |neuron| neuron == 4).unwrap(), 6);
+    assert_eq!(table.split_connection(1, 3, |neuron| neuron == 4).unwrap(), 6);
+    assert_eq!(table.split_connection(1, 3, |_| false).unwrap(), 4);
+    assert_eq!(table.split_neurons[&split], vec![4, 6]);
+
+    // After clearing, the same split makes a new neuron under the same innovation
+    table.clear_splits();
+    assert_eq!(table.split_connection(1, 3, |_| false).unwrap(), 7);
+    assert_eq!(*table.get_innovation((1, 3, Type::Neuron)).unwrap(), split);
+}